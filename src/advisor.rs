@@ -0,0 +1,106 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::heuristics::hit_damage;
+
+const SAMPLES: usize = 256;
+const SEED: u64 = 0x5eed; //fixed so the estimate doesn't flicker between frames or redraws
+
+const CELL_SIZE: Vec2<f32> = Vec2{ x: 0.1, y: 0.06 };
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.03, y: 0.03 };
+const NOTE_SIZE: Vec2<f32> = Vec2{ x: 0.02, y: 0.02 };
+
+//small deterministic generator, good enough for sampling and doesn't need another dependency
+pub struct Rng(pub u64);
+
+impl Rng {
+	pub fn next(&mut self) -> f64 {
+		self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+		(self.0 >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	pub fn below(&mut self, n: usize) -> usize {
+		((self.next() * n as f64) as usize).min(n.saturating_sub(1))
+	}
+}
+
+fn kill_time(attackers: &[Unit], defenders: &[Unit]) -> f64 {
+	let alive = defenders.iter().filter(|d| d.hp > 0.0).count();
+	if alive == 0 { return 0.0; }
+	let hp: f64 = defenders.iter().map(|d| d.hp.max(0.0)).sum();
+	let dps: f64 = attackers.iter().filter(|a| a.hp > 0.0).map(|a| {
+		defenders.iter().filter(|d| d.hp > 0.0).map(|d| hit_damage(a, d)).sum::<f64>() / alive as f64
+	}).sum();
+	let regen: f64 = defenders.iter().filter(|d| d.hp > 0.0).map(|d| d.regen).sum();
+	if dps - regen <= 1e-9 { std::f64::INFINITY } else { hp / (dps - regen) }
+}
+
+fn outcome(ours: &[Unit], theirs: &[Unit]) -> f64 {
+	let (a, b) = (kill_time(ours, theirs), kill_time(theirs, ours));
+	if a < b { 1.0 } else if a > b { 0.0 } else { 0.5 }
+}
+
+//fills in whatever the opponent hasn't revealed with a random guess
+pub fn sample(o: &UnitView, rng: &mut Rng) -> Unit {
+	let mut u = o.unit.clone();
+	if !o.class_revealed {
+		u.class = [Class::Melee, Class::Ranged][rng.below(2)];
+	}
+	if !o.element_revealed {
+		u.element = [Element::Red, Element::Green, Element::Blue][rng.below(3)];
+	}
+	if !o.frac_hp_revealed {
+		u.hp = u.max_hp * (1.0 - rng.next());
+	}
+	u
+}
+
+pub struct Advice {
+	pub win_chance: f64,
+	pub grid: Vec<Vec<f64>>, //chance of each of our units beating each of theirs one on one
+}
+
+impl Advice {
+	pub fn new(team: &[Unit], opponent: &[UnitView]) -> Self {
+		let mut rng = Rng(SEED);
+		let mut wins = 0.0;
+		let mut grid = vec!(vec!(0.0; opponent.len()); team.len());
+		for _ in 0..SAMPLES {
+			let theirs: Vec<Unit> = opponent.iter().map(|o| sample(o, &mut rng)).collect();
+			wins += outcome(team, &theirs);
+			for (i, u) in team.iter().enumerate() {
+				for (j, o) in theirs.iter().enumerate() {
+					grid[i][j] += outcome(std::slice::from_ref(u), std::slice::from_ref(o)) / SAMPLES as f64;
+				}
+			}
+		}
+		Self {
+			win_chance: wins / SAMPLES as f64,
+			grid,
+		}
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport) {
+		let columns = self.grid.get(0).map(|r| r.len()).unwrap_or(0);
+		let size = CELL_SIZE * vec2(columns + 1, self.grid.len() + 2).f32();
+		let pos = vec2(vp.right_edge() - 0.06 - size.x, 0.8 - size.y);
+		quad(v, pos.extend(0.0), size, Color(VERY_DARK_GREY));
+		let text = |v2: &mut Vec<Vertex>, p: Vec2<f32>, s: &str| draw_string(v2, (p + (CELL_SIZE - TEXT_SIZE) * 0.5).extend(10.0), TEXT_SIZE, s, None);
+		let row = |i: usize| pos + vec2(0.0, size.y - CELL_SIZE.y * (i + 1) as f32);
+		draw_string(v2, (row(0) + vec2(0.01, (CELL_SIZE.y - TEXT_SIZE.y) * 0.5)).extend(10.0), TEXT_SIZE, &format!("guess: {:.0}/100",self.win_chance * 100.0), None);
+		draw_string(v2, (pos + vec2(0.01, -NOTE_SIZE.y * 1.3)).extend(10.0), NOTE_SIZE, "estimate: guessed rules, no perks", None);
+		for j in 0..columns {
+			text(v2, row(1) + vec2(CELL_SIZE.x * (j + 1) as f32, 0.0), &format!("e{}",j + 1));
+		}
+		for (i, r) in self.grid.iter().enumerate() {
+			let p = row(i + 2);
+			text(v2, p, &format!("u{}",i + 1));
+			for (j, &chance) in r.iter().enumerate() {
+				let p = p + vec2(CELL_SIZE.x * (j + 1) as f32, 0.0);
+				let c = [(1.0 - chance as f32) * 0.6, chance as f32 * 0.6, 0.0, 1.0];
+				quad(v, p.extend(1.0), CELL_SIZE * 0.95, Color(c));
+				text(v2, p, &format!("{:.0}",chance * 100.0));
+			}
+		}
+	}
+}
@@ -0,0 +1,102 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::{team_unit_pos,opponent_unit_pos};
+
+const DOT_SIZE: f32 = 0.015;
+const DOTS_PER_LINE: usize = 16;
+const NUMBER_SIZE: Vec2<f32> = Vec2{ x: 0.035, y: 0.035 };
+const NUMBER_RISE: f32 = 0.12;
+const DEAD_FADE: f32 = 0.75; //not fully black so dead units can still be moused over
+
+pub fn smoothstep(f: f32) -> f32 {
+	let f = f.max(0.0).min(1.0);
+	f * f * (3.0 - 2.0 * f)
+}
+
+//units that are missing from the next snapshot are kept where they are, they get faded out as dead instead
+pub fn lerp_units<T: AsUnit + Clone>(from: &[T], to: &[T], f: f32) -> Vec<T> {
+	from.iter().enumerate().map(|(i, u)| {
+		let mut u = u.clone();
+		if let Some(next) = to.get(i) {
+			let (a, b) = (u.unit().hp, next.unit().hp);
+			u.unit_mut().hp = a + (b - a) * f as f64;
+		}
+		u
+	}).collect()
+}
+
+//snapshots don't record who hit who, so each hp drop is attributed to the living enemy lined up closest to the target
+pub fn draw_transition<T: AsUnit, O: AsUnit>(v2: &mut Vec<Vertex>, vp: &Viewport, t: (&[T], &[T]), o: (&[O], &[O]), f: f32) {
+	let tc = centers(t.0.len(), team_unit_pos);
+	let oc = centers(o.0.len(), opponent_unit_pos);
+	draw_side(v2, vp, t.0, t.1, &tc, o.0, &oc, f);
+	draw_side(v2, vp, o.0, o.1, &oc, t.0, &tc, f);
+}
+
+fn centers(len: usize, pos: fn(usize, usize, Vec2<f32>) -> Vec2<f32>) -> Vec<Vec2<f32>> {
+	(0..len).map(|i| pos(len, i, UNIT_SIZE) + UNIT_SIZE * 0.5).collect()
+}
+
+fn draw_side<T: AsUnit, A: AsUnit>(v2: &mut Vec<Vertex>, vp: &Viewport, units: &[T], next: &[T], centers: &[Vec2<f32>], attackers: &[A], attacker_centers: &[Vec2<f32>], f: f32) {
+	for i in 0..units.len() {
+		let before = units[i].unit().hp;
+		let after = next.get(i).map(|u| u.unit().hp).unwrap_or(0.0);
+		let delta = after - before;
+		if delta < -1e-8 {
+			if let Some(j) = attacker_for(i, units.len(), attackers) {
+				draw_attack(v2, vp, attackers[j].unit(), attacker_centers[j], centers[i], f);
+			}
+		}
+		if delta.abs() > 1e-8 {
+			let s = format!("{:+.1}",delta);
+			let pos = centers[i] + vec2(-NUMBER_SIZE.x * s.len() as f32 * 0.5, UNIT_SIZE.y * 0.5 + NUMBER_RISE * f);
+			draw_string_blended(v2, pos.extend(25.0), NUMBER_SIZE, s, 1.0 - f, [0.0; 4]);
+		}
+		let fade = if before <= 0.0 {
+			DEAD_FADE
+		} else if after <= 0.0 {
+			DEAD_FADE * f
+		} else {
+			0.0
+		};
+		if fade > 0.0 {
+			quad(v2, (centers[i] - UNIT_SIZE * 0.5).extend(9.0), UNIT_SIZE, Color([0.0, 0.0, 0.0, fade]));
+		}
+	}
+}
+
+fn attacker_for<A: AsUnit>(target: usize, targets: usize, attackers: &[A]) -> Option<usize> {
+	let slot = |i: usize, len: usize| (i as f32 + 0.5) / len as f32;
+	(0..attackers.len())
+		.filter(|&j| attackers[j].unit().hp > 0.0)
+		.min_by(|&a, &b| {
+			let da = (slot(a, attackers.len()) - slot(target, targets)).abs();
+			let db = (slot(b, attackers.len()) - slot(target, targets)).abs();
+			da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+		})
+}
+
+//melee hits flash a line between the two units, ranged ones send a projectile across
+fn draw_attack(v2: &mut Vec<Vertex>, vp: &Viewport, attacker: &Unit, from: Vec2<f32>, to: Vec2<f32>, f: f32) {
+	match attacker.class {
+		Class::Melee => {
+			let c = crate::settings::class_color(vp, Class::Melee);
+			let c = [c[0], c[1], c[2], 1.0 - f];
+			for k in 0..=DOTS_PER_LINE {
+				let p = from + (to - from) * (k as f32 / DOTS_PER_LINE as f32);
+				quad(v2, (p - vec2(DOT_SIZE, DOT_SIZE)).extend(8.0), vec2(DOT_SIZE, DOT_SIZE) * 2.0, Color(c));
+			}
+		},
+		Class::Ranged => if f < 0.5 {
+			let c = crate::settings::element_color(vp, attacker.element);
+			let t = f * 2.0;
+			for k in 0..3 {
+				let trail = (t - k as f32 * 0.05).max(0.0);
+				let p = from + (to - from) * trail;
+				let size = DOT_SIZE * (1.5 - k as f32 * 0.4);
+				quad(v2, (p - vec2(size, size)).extend(8.0), vec2(size, size) * 2.0, Color([c[0], c[1], c[2], 1.0 - k as f32 * 0.3]));
+			}
+		},
+	}
+}
@@ -1,12 +1,11 @@
 use crate::prelude::*;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{WebGlProgram,HtmlImageElement,WebGlShader,HtmlCanvasElement,WebSocket,MessageEvent,Blob,FileReader,Document};
+use web_sys::{WebGlProgram,HtmlImageElement,WebGlShader,HtmlCanvasElement,WebSocket,MessageEvent,Blob,FileReader};
 use js_sys::Uint8Array;
 use crate::vertex::*;
 use std::mem;
-use std::sync::atomic::AtomicU32;
-use std::sync::atomic::Ordering::Relaxed;
+use std::cell::Cell;
 
 const SALT: &[u8] = b"chisps";
 
@@ -65,12 +64,13 @@ pub fn link_program(
 	}
 }
 
-pub fn screen_coords(x: i32, y: i32, canvas: &HtmlCanvasElement) -> Vec2<f32> {
-	let x = x as f32;
-	let y = -y as f32;
-	let (x, y) = (x / canvas.client_width() as f32 * 2.0 - 1.0, y / canvas.client_height() as f32 * 2.0 + 1.0);
-	let x = x * canvas.client_width() as f32 / canvas.client_height() as f32; //multiply by aspect ratio so it will line up with aspect ratio rendered
-	vec2(x,y)
+pub fn query_param(name: &str) -> Option<String> {
+	let href = window().location().href().ok()?;
+	let query = href.splitn(2, '?').nth(1)?;
+	query.split('&').find_map(|p| {
+		let mut p = p.splitn(2, '=');
+		if p.next() == Some(name) { p.next().map(|v| v.to_string()) } else { None }
+	})
 }
 
 pub fn send(ws: &WebSocket, t: ClientPacket) -> Result<(), JsValue> {
@@ -150,7 +150,7 @@ pub fn load_textures(context: Rc<GL>) {
 	image.borrow().set_src("textures.png");
 }
 
-pub fn setup_rendering(canvas: &HtmlCanvasElement, document: &Document) -> Result<Rc<GL>, JsValue> {
+pub fn setup_rendering(canvas: &HtmlCanvasElement, viewport: Rc<Cell<Viewport>>) -> Result<Rc<GL>, JsValue> {
 	let context = Rc::new(canvas.get_context("webgl")?.expect("browser does not support webgl").dyn_into::<GL>()?);
 	
 	let vert_shader = compile_shader(
@@ -175,33 +175,22 @@ pub fn setup_rendering(canvas: &HtmlCanvasElement, document: &Document) -> Resul
 	context.vertex_attrib_pointer_with_i32(3, 1, GL::FLOAT, false, STRIDE, 12+16+8);
 	context.enable_vertex_attrib_array(0); context.enable_vertex_attrib_array(1); context.enable_vertex_attrib_array(2); context.enable_vertex_attrib_array(3);
 	
-	let aspect_ratio_location = context.get_uniform_location(&program, "aspect_ratio");
 	let context2 = context.clone();
 	let canvas2 = canvas.clone();
-	let body = document.body().expect("website had no body");
-	let aspect_ratio_location2 = aspect_ratio_location.clone();
+	let viewport2 = viewport.clone();
 	let onresize = Closure::wrap(Box::new(move|| {
-		let (w, h) = (body.client_width(), body.client_height());
-		context2.viewport(0, 0, w, h);
-		canvas2.set_attribute("width",&w.to_string()).expect("failed to set canvas width");
-		canvas2.set_attribute("height",&h.to_string()).expect("failed to set canvas height");
-		let a = w as f32 / h as f32;
-		ASPECT_RATIO.store(unsafe { mem::transmute(a) }, Relaxed);
-		context2.uniform1f(aspect_ratio_location2.as_ref(), a.recip());
+		let vp = window_viewport(viewport2.get());
+		viewport2.set(vp);
+		apply_viewport(&context2, &canvas2, &vp);
 	}) as Box<dyn Fn()>);
 	window().add_event_listener_with_callback("resize",onresize.as_ref().unchecked_ref()).expect("failed to add resize listener");
 	onresize.forget();
 	
 	load_textures(context.clone());
 	
-	let body = document.body().expect("no body present on document");
-	let (w, h) = (body.client_width(), body.client_height());
-	canvas.set_attribute("width",&w.to_string()).expect("failed to set canvas width");
-	canvas.set_attribute("height",&h.to_string()).expect("failed to set canvas height");
-	context.viewport(0, 0, w, h);
-	let a = w as f32 / h as f32;
-	ASPECT_RATIO.store(unsafe { mem::transmute(a) }, Relaxed);
-	context.uniform1f(aspect_ratio_location.as_ref(), a.recip());
+	let vp = window_viewport(viewport.get());
+	viewport.set(vp);
+	apply_viewport(&context, canvas, &vp);
 	
 	context.clear_color(0.0, 0.0, 0.0, 1.0);
 	context.enable(GL::DEPTH_TEST);
@@ -212,22 +201,22 @@ pub fn setup_rendering(canvas: &HtmlCanvasElement, document: &Document) -> Resul
 	Ok(context)
 }
 
-static ASPECT_RATIO: AtomicU32 = AtomicU32::new(unsafe { mem::transmute(1f32) });
-
-pub fn top_edge() -> f32 {
-	1.0
-}
-
-pub fn bottom_edge() -> f32 {
-	-1.0
-}
-
-pub fn left_edge() -> f32 {
-	- unsafe { mem::transmute/*::<_, f32>*/(ASPECT_RATIO.load(Relaxed)) }
+//only the window size and dpr are read again, the rest is kept from the old viewport
+pub fn window_viewport(old: Viewport) -> Viewport {
+	let w = window().inner_width().ok().and_then(|w| w.as_f64()).unwrap_or(1.0) as f32;
+	let h = window().inner_height().ok().and_then(|h| h.as_f64()).unwrap_or(1.0) as f32;
+	Viewport { display: old.display, ..Viewport::new(vec2(w, h), window().device_pixel_ratio() as f32, old.scale) }
 }
 
-pub fn right_edge() -> f32 {
-	unsafe { mem::transmute(ASPECT_RATIO.load(Relaxed)) }
+pub fn apply_viewport(context: &GL, canvas: &HtmlCanvasElement, vp: &Viewport) {
+	let size = vp.backing_size();
+	canvas.set_attribute("width",&size.x.to_string()).expect("failed to set canvas width");
+	canvas.set_attribute("height",&size.y.to_string()).expect("failed to set canvas height");
+	let (h, offset) = vp.letterbox();
+	context.viewport(0, (offset * vp.dpr).round() as i32, size.x as i32, (h * vp.dpr).round() as i32);
+	let program = context.get_parameter(GL::CURRENT_PROGRAM).ok().and_then(|p| p.dyn_into::<WebGlProgram>().ok()).expect("no shader program in use");
+	context.uniform1f(context.get_uniform_location(&program, "aspect_ratio").as_ref(), vp.aspect_ratio().recip());
+	context.uniform1f(context.get_uniform_location(&program, "scale").as_ref(), vp.scale);
 }
 
 pub fn hash(thing: &[u8]) -> [u64; 4] {
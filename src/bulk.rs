@@ -0,0 +1,108 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::cost;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.03, y: 0.03 };
+pub const PANEL_SIZE: Vec2<f32> = Vec2{ x: 0.6, y: 0.3 };
+const HIGHLIGHT: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+
+pub enum BulkAction {
+	Repair,
+	Juice,
+	Clear,
+}
+
+//items picked out of the equipment grids, as (in safe storage, index) since that's all a transfer needs
+pub struct BulkSelection {
+	pub items: Vec<(bool, usize)>,
+}
+
+impl BulkSelection {
+	pub fn new() -> Self {
+		Self { items: Vec::new() }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	pub fn contains(&self, item: (bool, usize)) -> bool {
+		self.items.contains(&item)
+	}
+
+	pub fn toggle(&mut self, item: (bool, usize)) {
+		match self.items.iter().position(|&x| x == item) {
+			Some(i) => { self.items.remove(i); },
+			None => self.items.push(item),
+		}
+	}
+
+	pub fn add(&mut self, item: (bool, usize)) {
+		if !self.contains(item) {
+			self.items.push(item);
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.items.clear();
+	}
+
+	fn equipment<'a>(&'a self, run: &'a [Equipment], safe: &'a [Equipment]) -> impl Iterator<Item=&'a Equipment> + 'a {
+		self.items.iter().filter_map(move |&(s, i)| if s { safe.get(i) } else { run.get(i) })
+	}
+
+	pub fn repair_cost(&self, run: &[Equipment], safe: &[Equipment], target: f64) -> f64 {
+		self.equipment(run, safe).map(|e| cost::repair(e, target)).sum()
+	}
+
+	//highest index first, so items that get used up don't shift the ones still to be sent
+	pub fn packets(&self) -> Vec<InventoryType> {
+		let mut items = self.items.clone();
+		items.sort_by(|a, b| b.cmp(a));
+		items.into_iter().map(|(s, i)| InventoryType::EquipmentStorage(s, i)).collect()
+	}
+
+	fn buttons(&self, pos: Vec2<f32>) -> Vec<(Button, BulkAction)> {
+		let size = vec2(PANEL_SIZE.x * 0.3, 0.06);
+		let y = pos.y + 0.02;
+		vec!(
+			(Button { name: "repair all".into(), pos: vec2(pos.x + 0.02, y), size, tex: Color(CYAN), edge: None }, BulkAction::Repair),
+			(Button { name: "juice all".into(), pos: vec2(pos.x + 0.02 + size.x * 1.1, y), size, tex: Color(PURPLE), edge: None }, BulkAction::Juice),
+			(Button { name: "clear".into(), pos: vec2(pos.x + 0.02 + size.x * 2.2, y), size, tex: Color(GREY), edge: None }, BulkAction::Clear),
+		)
+	}
+
+	pub fn click(&self, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>) -> Option<BulkAction> {
+		if self.is_empty() { return None; }
+		self.buttons(pos).into_iter().find(|(b, _)| b.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some()).map(|(_, a)| a)
+	}
+
+	//the totals double as the confirmation, nothing is sent until one of the buttons under them is pressed
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>, run: &[Equipment], safe: &[Equipment], target: f64, gold: f64) {
+		if self.is_empty() { return; }
+		quad(v, pos.extend(0.0), PANEL_SIZE, Color(VERY_DARK_GREY));
+		let repair = self.repair_cost(run, safe, target);
+		let lines = [
+			(format!("{} selected",self.items.len()), DARK_GREY),
+			(format!("repair to {:.3}: ~{}",target,cost::label(repair)), if cost::shortfall(repair, gold).is_some() { DULL_RED } else { DARK_GREY }),
+			(format!("juice all: {} items destroyed",self.items.len()), DARK_GREY),
+		];
+		for (i, (s, c)) in lines.iter().enumerate() {
+			let p = pos + vec2(0.02, PANEL_SIZE.y - TEXT_SIZE.y * 1.3 * (i + 1) as f32);
+			draw_string(v2, p.extend(10.0), TEXT_SIZE, s, Some((Color(*c), s.len())));
+		}
+		for (b, a) in self.buttons(pos) {
+			b.draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+			if let BulkAction::Repair = a {
+				if let Some(reason) = cost::shortfall(repair, gold) {
+					cost::draw_disabled(v2, vp, m, &b, &reason);
+				}
+			}
+		}
+	}
+
+	pub fn draw_highlight(&self, v: &mut Vec<Vertex>, pos: Vec2<f32>, size: Vec2<f32>) {
+		quad(v, (pos - size * 0.08).extend(9.0), size * 1.16, Color(HIGHLIGHT));
+	}
+}
@@ -0,0 +1,141 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.025, y: 0.025 };
+const LINES: usize = 28;
+const ROWS: usize = LINES - 1; //the top line is kept for the note
+const WIDTH: usize = 36;
+const NOTE: &str = "blocks aren't recorded, not logged"; //so a hit that did nothing isn't mistaken for a missing log line
+
+pub struct LogEntry {
+	pub step: usize, //the snapshot the event happens after
+	pub text: String,
+}
+
+pub struct CombatLog {
+	pub entries: Vec<LogEntry>,
+	pub scroll: usize,
+	pub follow: bool, //keeps the replay cursor in view until the player scrolls away
+}
+
+impl CombatLog {
+	pub fn new(r: &FightRecording) -> Self {
+		let mut entries = Vec::new();
+		for i in 1..r.snapshots.len() {
+			let (t0, o0) = r.get(i - 1);
+			let (t1, o1) = r.get(i);
+			diff(&mut entries, i - 1, "ally", &t0[..], &t1[..]);
+			diff(&mut entries, i - 1, "enemy", &o0[..], &o1[..]);
+		}
+		Self {
+			entries,
+			scroll: 0,
+			follow: true,
+		}
+	}
+
+	pub fn pos(&self, vp: &Viewport) -> Vec2<f32> {
+		vec2(vp.left_edge() + 0.02, vp.top_edge() - 0.1) - vec2(0.0, self.size().y)
+	}
+
+	pub fn size(&self) -> Vec2<f32> {
+		TEXT_SIZE * vec2(WIDTH, LINES).f32()
+	}
+
+	pub fn collides(&self, vp: &Viewport, m: Vec2<f32>) -> bool {
+		rect(m, self.pos(vp), self.size())
+	}
+
+	fn line_at(&self, vp: &Viewport, m: Vec2<f32>) -> Option<usize> {
+		if !self.collides(vp, m) { return None; }
+		let row = ((self.pos(vp).y + self.size().y - m.y) / TEXT_SIZE.y) as usize;
+		row.checked_sub(1).map(|row| self.scroll + row).filter(|&i| i < self.entries.len())
+	}
+
+	//returns the snapshot to seek to
+	pub fn clicked(&mut self, vp: &Viewport, m: Vec2<f32>) -> Option<usize> {
+		let step = self.line_at(vp, m).map(|i| self.entries[i].step);
+		if step.is_some() {
+			self.follow = true;
+		}
+		step
+	}
+
+	pub fn scroll_by(&mut self, lines: isize) {
+		let max = self.entries.len().saturating_sub(ROWS) as isize;
+		self.scroll = (self.scroll as isize + lines).max(0).min(max) as usize;
+		self.follow = false;
+	}
+
+	pub fn draw(&mut self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, current: usize) {
+		if self.follow {
+			let i = self.entries.iter().position(|e| e.step >= current).unwrap_or(self.entries.len());
+			self.scroll = i.saturating_sub(ROWS / 2).min(self.entries.len().saturating_sub(ROWS));
+		}
+		let pos = self.pos(vp);
+		let size = self.size();
+		quad(v, pos.extend(0.0), size, Color([VERY_DARK_GREY[0], VERY_DARK_GREY[1], VERY_DARK_GREY[2], 0.8]));
+		draw_string(v2, (pos + vec2(0.0, size.y - TEXT_SIZE.y)).extend(2.0), TEXT_SIZE, NOTE, Some((Color(DARK_GREY), NOTE.len())));
+		let hovered = self.line_at(vp, m);
+		for (i, e) in self.entries.iter().enumerate().skip(self.scroll).take(ROWS) {
+			let p = pos + vec2(0.0, size.y - TEXT_SIZE.y * (i - self.scroll + 2) as f32);
+			if e.step == current || hovered == Some(i) {
+				let c = if e.step == current { DARK_GREY } else { [DARK_GREY[0], DARK_GREY[1], DARK_GREY[2], 0.5] };
+				quad(v, p.extend(1.0), vec2(size.x, TEXT_SIZE.y), Color(c));
+			}
+			let text: String = e.text.chars().take(WIDTH).collect();
+			draw_string(v2, p.extend(2.0), TEXT_SIZE, &text, None);
+		}
+	}
+}
+
+//snapshots only hold unit states, so events are inferred from how hp changed between them
+//blocks leave no trace in hp, so they can't be logged and the note at the top of the log says so
+//regen and heals look the same from hp alone, so gains aren't put down to either
+fn diff<T: AsUnit>(entries: &mut Vec<LogEntry>, step: usize, side: &str, before: &[T], after: &[T]) {
+	for (i, u) in before.iter().enumerate() {
+		let u = u.unit();
+		if u.hp <= 0.0 { continue; }
+		let hp = after.get(i).map(|a| a.unit().hp).unwrap_or(0.0);
+		let name = format!("{}: {} {}",step + 1,side,i + 1);
+		if hp < u.hp - 1e-8 {
+			entries.push(LogEntry { step, text: format!("{} lost {:.2} hp",name,u.hp - hp.max(0.0)) });
+		} else if hp > u.hp + 1e-8 {
+			entries.push(LogEntry { step, text: format!("{} gained {:.2} hp",name,hp - u.hp) });
+		}
+		if hp <= 0.0 {
+			entries.push(LogEntry { step, text: format!("{} died",name) });
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::inventory::tests::unit;
+
+	fn log(before: &[f64], after: &[f64]) -> Vec<String> {
+		let (before, after): (Vec<Unit>, Vec<Unit>) = (before.iter().cloned().map(unit).collect(), after.iter().cloned().map(unit).collect());
+		let mut entries = Vec::new();
+		diff(&mut entries, 4, "ally", &before, &after);
+		assert!(entries.iter().all(|e| e.step == 4));
+		entries.into_iter().map(|e| e.text).collect()
+	}
+
+	#[test]
+	fn hp_changes_are_logged() {
+		assert_eq!(log(&[10.0, 5.0, 3.0], &[7.5, 5.0, 4.0]), vec!("5: ally 1 lost 2.50 hp", "5: ally 3 gained 1.00 hp"));
+	}
+
+	#[test]
+	fn deaths_are_logged_once() {
+		assert_eq!(log(&[2.0, 0.0], &[-1.0, 0.0]), vec!("5: ally 1 lost 2.00 hp", "5: ally 1 died"));
+		assert_eq!(log(&[2.0], &[]), vec!("5: ally 1 lost 2.00 hp", "5: ally 1 died"));
+	}
+
+	#[test]
+	fn nothing_is_logged_without_a_change() {
+		assert!(log(&[1.0, 2.0], &[1.0, 2.0]).is_empty());
+	}
+}
@@ -0,0 +1,55 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+
+//prices in gold, so actions can be labelled and checked before anything gets sent
+//the server has the final say, anything it turns down still comes back as a message
+const DISABLED: [f32; 4] = [0.1, 0.1, 0.1, 0.75];
+const REASON_SIZE: Vec2<f32> = Vec2{ x: 0.03, y: 0.03 };
+
+//why the player can't pay, if they can't
+pub fn shortfall(price: f64, gold: f64) -> Option<String> {
+	if price > gold + 1e-8 {
+		Some(format!("needs {:.2} more gold",price - gold))
+	} else {
+		None
+	}
+}
+
+pub fn label(price: f64) -> String {
+	format!("{:.2} gold",price)
+}
+
+//the formula the heal buttons were already labelled with, from HEAL_COST in lib
+pub fn heal(u: &Unit) -> f64 {
+	(1.0 - u.hp / u.max_hp) * HEAL_COST * (u.perks.len() + 5 + u.perk_choice.is_some() as usize) as f64 / 5.0
+}
+
+pub fn heal_all(team: &[Unit]) -> f64 {
+	team.iter().map(heal).sum()
+}
+
+//the cheapest heal that can still be bought, heal all heals as many units as the gold covers
+pub fn cheapest_heal(team: &[Unit]) -> Option<f64> {
+	team.iter().map(heal).filter(|&c| c > 1e-8).fold(None, |min, c| Some(min.map_or(c, |m: f64| m.min(c))))
+}
+
+//an estimate, lib doesn't have the server's repair formula so this assumes repair_cost is per point of durability
+pub fn repair(e: &Equipment, target: f64) -> f64 {
+	(target - e.durability).max(0.0) * e.repair_cost
+}
+
+pub fn unit_purchase() -> f64 {
+	UNIT_COST
+}
+
+//greys out a button that can't be afforded and says why when it's hovered
+pub fn draw_disabled(v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, b: &Button, reason: &str) {
+	let pos = b.pos(vp);
+	quad(v2, pos.extend(11.0), b.size, Color(DISABLED));
+	if rect(m, pos, b.size) {
+		let mut p = m + vec2(0.0, REASON_SIZE.y);
+		p.x = p.x.min(vp.right_edge() - REASON_SIZE.x * reason.len() as f32);
+		draw_string(v2, p.extend(30.0), REASON_SIZE, reason, Some((Color(VERY_DARK_GREY), reason.len())));
+	}
+}
@@ -0,0 +1,154 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::storage;
+use serde::{Serialize,Deserialize};
+
+const STORAGE_KEY: &str = "dont_ask";
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.03, y: 0.03 };
+const DIALOG_SIZE: Vec2<f32> = Vec2{ x: 1.3, y: 0.45 };
+const BUTTON: Vec2<f32> = Vec2{ x: 0.3, y: 0.07 };
+const SHADE: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const PANEL: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
+const Z: f32 = 40.0;
+
+//the actions that can't be taken back once the packet is sent
+#[derive(Debug,Clone,Copy,PartialEq,Serialize,Deserialize)]
+pub enum ConfirmKind {
+	Move,
+	Fight,
+	Juice,
+	Purchase,
+}
+
+impl ConfirmKind {
+	pub const ALL: [ConfirmKind; 4] = [ConfirmKind::Move, ConfirmKind::Fight, ConfirmKind::Juice, ConfirmKind::Purchase];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			ConfirmKind::Move => "move",
+			ConfirmKind::Fight => "fight",
+			ConfirmKind::Juice => "juice",
+			ConfirmKind::Purchase => "purchase",
+		}
+	}
+}
+
+pub enum DialogResult {
+	Confirm(ConfirmKind, Vec<ClientPacket>),
+	Cancel,
+}
+
+struct Dialog {
+	kind: ConfirmKind,
+	lines: Vec<String>,
+	packets: Vec<ClientPacket>,
+	dont_ask: bool,
+}
+
+//one question at a time, everything else waits until it's answered
+pub struct Confirmations {
+	pub skip: Vec<ConfirmKind>,
+	open: Option<Dialog>,
+}
+
+impl Confirmations {
+	pub fn load() -> Self {
+		Self { skip: storage::load(STORAGE_KEY).unwrap_or_default(), open: None }
+	}
+
+	fn save(&self) {
+		storage::save(STORAGE_KEY, &self.skip);
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.open.is_some()
+	}
+
+	pub fn set_asking(&mut self, kind: ConfirmKind, ask: bool) {
+		self.skip.retain(|&k| k != kind);
+		if !ask {
+			self.skip.push(kind);
+		}
+		self.save();
+	}
+
+	//hands the packets straight back if this kind of action isn't being asked about, otherwise they wait for an answer
+	pub fn ask(&mut self, kind: ConfirmKind, lines: Vec<String>, packets: Vec<ClientPacket>) -> Option<Vec<ClientPacket>> {
+		if self.skip.contains(&kind) {
+			return Some(packets);
+		}
+		self.open = Some(Dialog { kind, lines, packets, dont_ask: false });
+		None
+	}
+
+	fn pos(&self) -> Vec2<f32> {
+		-DIALOG_SIZE * 0.5
+	}
+
+	fn buttons(&self) -> (Button, Button, Button) {
+		let pos = self.pos();
+		let dont_ask = self.open.as_ref().map(|d| d.dont_ask).unwrap_or(false);
+		(
+			Button { name: "confirm".into(), pos: pos + vec2(DIALOG_SIZE.x - BUTTON.x * 2.2 - 0.03, 0.03), size: BUTTON, tex: Color(GREEN), edge: None },
+			Button { name: "cancel".into(), pos: pos + vec2(DIALOG_SIZE.x - BUTTON.x * 1.1 - 0.03, 0.03), size: BUTTON, tex: Color(GREY), edge: None },
+			Button { name: format!("[{}] don't ask again",if dont_ask { "x" } else { " " }), pos: pos + vec2(0.03, 0.03), size: vec2(0.45, BUTTON.y), tex: Color(DARK_GREY), edge: None },
+		)
+	}
+
+	fn answer(&mut self, confirm: bool) -> Option<DialogResult> {
+		let d = self.open.take()?;
+		if !confirm {
+			return Some(DialogResult::Cancel);
+		}
+		if d.dont_ask {
+			self.set_asking(d.kind, false);
+		}
+		Some(DialogResult::Confirm(d.kind, d.packets))
+	}
+
+	//clicking outside the dialog counts as cancelling it
+	pub fn click(&mut self, vp: &Viewport, m: Vec2<f32>) -> Option<DialogResult> {
+		if !self.is_open() { return None; }
+		let (confirm, cancel, dont_ask) = self.buttons();
+		if confirm.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+			self.answer(true)
+		} else if dont_ask.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+			if let Some(d) = self.open.as_mut() {
+				d.dont_ask = !d.dont_ask;
+			}
+			None
+		} else if cancel.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() || !rect(m, self.pos(), DIALOG_SIZE) {
+			self.answer(false)
+		} else {
+			None
+		}
+	}
+
+	pub fn key(&mut self, key: &str) -> Option<DialogResult> {
+		match key {
+			"Enter" | "y" => self.answer(true),
+			"Escape" | "n" => self.answer(false),
+			_ => None,
+		}
+	}
+
+	pub fn draw(&self, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		let d = match &self.open {
+			Some(d) => d,
+			None => return,
+		};
+		quad(v2, vec2(vp.left_edge(), vp.bottom_edge()).extend(Z - 1.0), vec2(vp.right_edge() - vp.left_edge(), vp.top_edge() - vp.bottom_edge()), Color(SHADE));
+		quad(v2, self.pos().extend(Z), DIALOG_SIZE, Color(PANEL));
+		for (i, s) in d.lines.iter().enumerate() {
+			let p = self.pos() + vec2(0.04, DIALOG_SIZE.y - TEXT_SIZE.y * 1.5 * (i + 1) as f32 - 0.02);
+			draw_string(v2, p.extend(Z + 2.0), TEXT_SIZE, s, None);
+		}
+		let hint = "enter to confirm, escape to cancel";
+		draw_string(v2, (self.pos() + vec2(0.04, BUTTON.y + 0.06)).extend(Z + 2.0), TEXT_SIZE * 0.7, hint, None);
+		let (confirm, cancel, dont_ask) = self.buttons();
+		confirm.draw_over(v2, vp, m, Z + 1.0);
+		cancel.draw_over(v2, vp, m, Z + 1.0);
+		dont_ask.draw_over(v2, vp, m, Z + 1.0);
+	}
+}
@@ -0,0 +1,121 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.025, y: 0.025 };
+const NODE_SIZE: Vec2<f32> = Vec2{ x: 0.04, y: 0.04 };
+const STEP: f32 = 0.1;
+const SHOWN: usize = 8;
+const TRACK: [f32; 4] = [0.3, 0.3, 0.3, 0.6];
+
+#[derive(Clone,Copy)]
+pub enum Route {
+	Up,
+	Stay,
+	Down,
+}
+
+impl Route {
+	pub fn from_move(i: usize) -> Self {
+		match i { 0 => Route::Up, 1 => Route::Stay, _ => Route::Down }
+	}
+
+	fn name(self) -> &'static str {
+		match self { Route::Up => "up", Route::Stay => "stay", Route::Down => "down" }
+	}
+}
+
+pub struct Level {
+	pub depth: usize,
+	pub won: usize,
+	pub lost: usize,
+	pub avoided: usize,
+	pub loot: usize,
+	pub gold: f64,
+	pub left_by: Option<Route>,
+}
+
+impl Level {
+	fn new(depth: usize) -> Self {
+		Self { depth, won: 0, lost: 0, avoided: 0, loot: 0, gold: 0.0, left_by: None }
+	}
+
+	fn color(&self) -> [f32; 4] {
+		if self.lost > 0 { DULL_RED } else if self.won > 0 { GREEN } else if self.avoided > 0 { CYAN } else { GREY }
+	}
+
+	fn label(&self) -> String {
+		let mut s = format!("depth {}",self.depth);
+		if self.won > 0 { s.push_str(&format!("  won {}",self.won)); }
+		if self.lost > 0 { s.push_str(&format!("  lost {}",self.lost)); }
+		if self.avoided > 0 { s.push_str(&format!("  avoided {}",self.avoided)); }
+		if self.loot > 0 { s.push_str(&format!("  loot {}",self.loot)); }
+		if self.gold.abs() > 1e-8 { s.push_str(&format!("  {:+.2} gold",self.gold)); }
+		if let Some(r) = self.left_by { s.push_str(&format!("  -> {}",r.name())); }
+		s
+	}
+}
+
+//every level visited since leaving the safe zone, newest last
+pub struct DiveMap {
+	pub levels: Vec<Level>,
+	gold: Option<f64>,
+}
+
+impl DiveMap {
+	pub fn new() -> Self {
+		Self { levels: Vec::new(), gold: None }
+	}
+
+	pub fn reset(&mut self) {
+		self.levels.clear();
+		self.gold = None;
+	}
+
+	//the server's depth is the real one, the guesses made when a move is clicked aren't recorded
+	pub fn visit(&mut self, depth: usize, gold: f64) {
+		if self.levels.last().map(|l| l.depth != depth).unwrap_or(true) {
+			self.levels.push(Level::new(depth));
+		}
+		if let (Some(before), Some(l)) = (self.gold, self.levels.last_mut()) {
+			l.gold += gold - before;
+		}
+		self.gold = Some(gold);
+	}
+
+	pub fn choose(&mut self, r: Route) {
+		if let Some(l) = self.levels.last_mut() {
+			l.left_by = Some(r);
+		}
+	}
+
+	pub fn avoided(&mut self) {
+		if let Some(l) = self.levels.last_mut() {
+			l.avoided += 1;
+		}
+	}
+
+	pub fn fought(&mut self, won: bool, loot: usize) {
+		if let Some(l) = self.levels.last_mut() {
+			if won { l.won += 1 } else { l.lost += 1 }
+			l.loot += loot;
+		}
+	}
+
+	//a vertical track down the left side, deepest level at the bottom
+	pub fn draw(&self, v2: &mut Vec<Vertex>, vp: &Viewport) {
+		if self.levels.is_empty() { return; }
+		let x = vp.left_edge() + crate::TEXT_SIZE.x;
+		let top = vp.top_edge() - crate::TEXT_SIZE.y * 3.0;
+		let shown = &self.levels[self.levels.len().saturating_sub(SHOWN)..];
+		if shown.len() > 1 {
+			quad(v2, vec3(x + NODE_SIZE.x * 0.4, top - STEP * (shown.len() - 1) as f32, 9.0), vec2(NODE_SIZE.x * 0.2, STEP * (shown.len() - 1) as f32), Color(TRACK));
+		}
+		for (i, l) in shown.iter().enumerate() {
+			let p = vec2(x, top - STEP * i as f32 - NODE_SIZE.y * 0.5);
+			quad(v2, p.extend(10.0), NODE_SIZE, Color(l.color()));
+			let s = l.label();
+			draw_string(v2, (p + vec2(NODE_SIZE.x * 1.5, (NODE_SIZE.y - TEXT_SIZE.y) * 0.5)).extend(10.0), TEXT_SIZE, &s, Some((Color(VERY_DARK_GREY), s.len())));
+		}
+	}
+}
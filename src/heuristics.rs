@@ -0,0 +1,48 @@
+use crate::prelude::*;
+
+//none of this comes from the server, lib only carries the stats and not the rules that use them
+//so the advisor and the simulator share these guesses, and anything they show is labelled as an estimate
+//if the real combat rules ever get exposed by lib these should be replaced by them
+//perks are left out entirely, only their descriptions reach the client and there's nothing to read their effects from
+
+//assumed wheels: red beats green beats blue beats red, and melee beats ranged
+pub const ELEMENT_ADVANTAGE: f64 = 1.5; //guessed damage multiplier against the element that's beaten
+pub const CLASS_ADVANTAGE: f64 = 1.25; //guessed damage multiplier against the class that's beaten
+pub const MAX_BLOCK: f64 = 0.9; //guessed cap, so stacking block can't make a unit untouchable
+
+fn beats(a: Element, b: Element) -> bool {
+	match (a, b) {
+		(Element::Red, Element::Green) | (Element::Green, Element::Blue) | (Element::Blue, Element::Red) => true,
+		_ => false,
+	}
+}
+
+pub fn multiplier(attacker: &Unit, defender: &Unit) -> f64 {
+	let e = if beats(attacker.element, defender.element) {
+		ELEMENT_ADVANTAGE
+	} else if beats(defender.element, attacker.element) {
+		ELEMENT_ADVANTAGE.recip()
+	} else {
+		1.0
+	};
+	let c = match (attacker.class, defender.class) {
+		(Class::Melee, Class::Ranged) => CLASS_ADVANTAGE,
+		(Class::Ranged, Class::Melee) => CLASS_ADVANTAGE.recip(),
+		_ => 1.0,
+	};
+	e * c
+}
+
+//damage of a hit that isn't blocked
+pub fn raw_damage(attacker: &Unit, defender: &Unit) -> f64 {
+	(attacker.attack * multiplier(attacker, defender) - defender.armor).max(0.0)
+}
+
+pub fn block_chance(defender: &Unit) -> f64 {
+	defender.block.max(0.0).min(MAX_BLOCK)
+}
+
+//expected damage of one hit, block is treated as the fraction of hits that get stopped
+pub fn hit_damage(attacker: &Unit, defender: &Unit) -> f64 {
+	raw_damage(attacker, defender) * (1.0 - block_chance(defender))
+}
@@ -0,0 +1,197 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::inventory::equip_slot;
+
+const MAX_HISTORY: usize = 20;
+const SHOWN: usize = 5;
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.025, y: 0.025 };
+
+pub struct Entry {
+	pub undo: Option<(InventoryType, InventoryType)>,
+	label: String,
+}
+
+//transfers the player made by hand, newest last, so misdrops can be put back
+pub struct TransferHistory {
+	pub entries: Vec<Entry>,
+}
+
+fn describe(i: &InventoryType) -> String {
+	use InventoryType::*;
+	match *i {
+		Team(i) => format!("team {}",i + 1),
+		UnitStorage(_) => "unit storage".to_string(),
+		EquipmentStorage(false, _) => "equipment".to_string(),
+		EquipmentStorage(true, _) => "safe storage".to_string(),
+		Unit{ in_team: true, uidx, eidx } => format!("team {} slot {}",uidx + 1,equip_slot(eidx) + 1),
+		Unit{ in_team: false, uidx, eidx } => format!("stored unit {} slot {}",uidx + 1,equip_slot(eidx) + 1),
+	}
+}
+
+fn item_name(i: &InventoryType, team: &[Unit], storage: Option<(&Vec<Unit>, &Vec<Equipment>)>, run: &[Equipment]) -> Option<String> {
+	use InventoryType::*;
+	let units = |in_team: bool| if in_team { Some(team) } else { storage.map(|(us, _es)| &us[..]) };
+	match *i {
+		Team(i) => team.get(i).map(|_| "unit".to_string()),
+		UnitStorage(i) => units(false)?.get(i).map(|_| "unit".to_string()),
+		EquipmentStorage(false, i) => run.get(i).map(|e| e.stat_name1().to_lowercase()),
+		EquipmentStorage(true, i) => storage?.1.get(i).map(|e| e.stat_name1().to_lowercase()),
+		Unit{ in_team, uidx, eidx } => units(in_team)?.get(uidx)?.equipment[equip_slot(eidx)].as_ref().map(|e| e.stat_name1().to_lowercase()),
+	}
+}
+
+//the transfer that puts things back, worked out from the state before the transfer was made
+//appended items always land at the end of their list, so that's where they're taken back from
+pub fn inverse(from: &InventoryType, to: &InventoryType, team: &[Unit], storage: Option<(&Vec<Unit>, &Vec<Equipment>)>, run: &[Equipment]) -> Option<(InventoryType, InventoryType)> {
+	use InventoryType::*;
+	let unit_storage = storage.map(|(us, _es)| us.len());
+	let equipment_len = |safe: bool| if safe { storage.map(|(_us, es)| es.len()) } else { Some(run.len()) };
+	let occupied = |in_team: bool, uidx: usize, eidx: EquipType| {
+		let units = if in_team { Some(team) } else { storage.map(|(us, _es)| &us[..]) };
+		units.and_then(|us| us.get(uidx)).map(|u| u.equipment[equip_slot(eidx)].is_some())
+	};
+	match (from, to) {
+		(&Team(i), &Team(j)) => {
+			let j = j.min(team.len().checked_sub(1)?);
+			if i == j { None } else { Some((Team(j), Team(i))) }
+		},
+		(&UnitStorage(_), &Team(j)) => Some((Team(j.min(team.len())), UnitStorage(unit_storage?.checked_sub(1)?))),
+		(&Team(i), &UnitStorage(_)) => Some((UnitStorage(unit_storage?), Team(i))),
+		(&EquipmentStorage(s, _), &EquipmentStorage(s2, _)) if s != s2 => Some((EquipmentStorage(s2, equipment_len(s2)?), EquipmentStorage(s, equipment_len(s)?.checked_sub(1)?))),
+		(&EquipmentStorage(s, _), &Unit{ in_team, uidx, eidx }) => {
+			let slot = Unit{ in_team, uidx, eidx };
+			if occupied(in_team, uidx, eidx)? {
+				//the old item went to the end of the list the new one came from, swapping it back in sends the new one there too
+				Some((EquipmentStorage(s, equipment_len(s)?.checked_sub(1)?), slot))
+			} else {
+				Some((slot, EquipmentStorage(s, equipment_len(s)?.checked_sub(1)?)))
+			}
+		},
+		(&Unit{ in_team, uidx, eidx }, &EquipmentStorage(s, _)) => Some((EquipmentStorage(s, equipment_len(s)?), Unit{ in_team, uidx, eidx })),
+		(&Unit{ in_team, uidx, eidx }, &Unit{ in_team: in_team2, uidx: uidx2, eidx: eidx2 }) => {
+			if in_team == in_team2 && uidx == uidx2 && equip_slot(eidx) == equip_slot(eidx2) { None } else { Some((to.clone(), from.clone())) }
+		},
+		_ => None,
+	}
+}
+
+impl TransferHistory {
+	pub fn new() -> Self {
+		Self { entries: Vec::new() }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.entries.clear();
+	}
+
+	//called before the transfer is applied anywhere, since the inverse depends on where things were
+	pub fn record(&mut self, from: &InventoryType, to: &InventoryType, team: &[Unit], storage: Option<(&Vec<Unit>, &Vec<Equipment>)>, run: &[Equipment]) {
+		let undo = inverse(from, to, team, storage, run);
+		let item = item_name(from, team, storage, run).unwrap_or_else(|| "item".to_string());
+		self.entries.push(Entry { undo, label: format!("{}: {} to {}",item,describe(from),describe(to)) });
+		if self.entries.len() > MAX_HISTORY {
+			self.entries.remove(0);
+		}
+	}
+
+	//the transfer to send, anything older can't be undone once something in the way couldn't be
+	pub fn undo(&mut self) -> Result<(InventoryType, InventoryType), String> {
+		let e = self.entries.pop().ok_or("nothing to undo")?;
+		e.undo.ok_or_else(|| {
+			self.entries.clear();
+			format!("can't undo {}",e.label)
+		})
+	}
+
+	pub fn button(&self, vp: &Viewport) -> Button {
+		Button {
+			name: "undo".to_string(),
+			pos: vec2(vp.left_edge() + crate::TEXT_SIZE.x, -0.44),
+			size: BUTTON_SIZE,
+			tex: Color(if self.entries.last().map(|e| e.undo.is_some()).unwrap_or(false) { YELLOW } else { GREY }),
+			edge: None,
+		}
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		if self.is_empty() { return; }
+		let b = self.button(vp);
+		b.draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+		let mut p = b.pos(vp) - vec2(0.0, TEXT_SIZE.y * 1.3);
+		for e in self.entries.iter().rev().take(SHOWN) {
+			let c = if e.undo.is_some() { DARK_GREY } else { DULL_RED };
+			draw_string(v2, p.extend(10.0), TEXT_SIZE, &e.label, Some((Color(c), e.label.len())));
+			p.y -= TEXT_SIZE.y * 1.3;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::inventory::Inventory;
+	use crate::inventory::tests::{blank,unit,hps};
+
+	fn equipment(durability: f64) -> Equipment {
+		let mut e: Equipment = blank();
+		e.durability = durability;
+		e
+	}
+
+	type Lists = (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>);
+
+	fn lists(team: &[Unit], unit_storage: &[Unit], equipment_storage: &[Equipment], run: &[Equipment]) -> Lists {
+		let d = |es: &[Equipment]| es.iter().map(|e| e.durability).collect();
+		(hps(team), hps(unit_storage), d(equipment_storage), d(run))
+	}
+
+	//makes the transfer and then its inverse, returning whether there was one and what everything looked like after
+	fn round_trip(from: InventoryType, to: InventoryType, team: &[f64], unit_storage: &[f64], run: &[f64], safe: &[f64]) -> (bool, Lists, Lists) {
+		let mut team: Vec<Unit> = team.iter().cloned().map(unit).collect();
+		let mut us: Vec<Unit> = unit_storage.iter().cloned().map(unit).collect();
+		let mut run: Vec<Equipment> = run.iter().cloned().map(equipment).collect();
+		let mut es: Vec<Equipment> = safe.iter().cloned().map(equipment).collect();
+		let before = lists(&team, &us, &es, &run);
+		let undo = inverse(&from, &to, &team, Some((&us, &es)), &run);
+		let mut inventory = Inventory{ team: &mut team, storage: Some((&mut us, &mut es)), equipment: &mut run };
+		assert!(inventory.transfer(&from, &to));
+		if let Some((from, to)) = &undo {
+			assert!(inventory.transfer(from, to));
+		}
+		(undo.is_some(), before, lists(&team, &us, &es, &run))
+	}
+
+	#[test]
+	fn moving_within_the_team_is_undone() {
+		let (undone, before, after) = round_trip(InventoryType::Team(0), InventoryType::Team(2), &[1.0, 2.0, 3.0], &[], &[], &[]);
+		assert!(undone);
+		assert_eq!(before, after);
+		let (undone, before, after) = round_trip(InventoryType::Team(2), InventoryType::Team(7), &[1.0, 2.0, 3.0], &[], &[], &[]);
+		assert!(!undone);
+		assert_eq!(before, after);
+	}
+
+	#[test]
+	fn units_are_taken_back_from_the_end_of_storage() {
+		let (undone, before, after) = round_trip(InventoryType::Team(1), InventoryType::UnitStorage(0), &[1.0, 2.0, 3.0], &[4.0], &[], &[]);
+		assert!(undone);
+		assert_eq!(before, after);
+		let (undone, before, after) = round_trip(InventoryType::UnitStorage(0), InventoryType::Team(1), &[1.0, 2.0], &[3.0], &[], &[]);
+		assert!(undone);
+		assert_eq!(before, after);
+	}
+
+	#[test]
+	fn equipment_is_moved_back_between_storages() {
+		let (undone, before, after) = round_trip(InventoryType::EquipmentStorage(false, 1), InventoryType::EquipmentStorage(true, 0), &[], &[], &[0.1, 0.2, 0.3], &[0.4]);
+		assert!(undone);
+		let (run, safe) = (before.3, before.2);
+		//the item comes back to the end of the list it came from rather than its old place
+		assert_eq!((after.3, after.2), (vec!(run[0], run[2], run[1]), safe));
+	}
+}
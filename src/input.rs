@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+pub const LONG_PRESS_MS: f64 = 500.0;
+pub const TAP_DISTANCE: f32 = 2e-2;
+pub const NO_POINTER: Vec2<f32> = Vec2{ x: 1e6, y: 1e6 }; //somewhere nothing collides with, so touch screens don't leave a stale mouseover behind
+
+#[derive(Debug,Copy,Clone)]
+pub struct Press {
+	pub pos: Vec2<f32>,
+	pub start: f64,
+	pub touch: bool,
+	pub moved: bool,
+	pub long: bool,
+}
+
+impl Press {
+	pub fn new(pos: Vec2<f32>, touch: bool) -> Self {
+		Self {
+			pos,
+			start: js_sys::Date::now(),
+			touch,
+			moved: false,
+			long: false,
+		}
+	}
+
+	pub fn is_tap(&self, pos: Vec2<f32>) -> bool {
+		(self.pos - pos).magnitude() < TAP_DISTANCE
+	}
+
+	//a touch held in place long enough opens the mouseover that a mouse would get by hovering
+	pub fn is_long(&self, now: f64) -> bool {
+		self.touch && !self.moved && now - self.start >= LONG_PRESS_MS
+	}
+}
@@ -0,0 +1,348 @@
+use crate::prelude::*;
+use crate::vertex::*;
+
+const PENDING_TIMEOUT: f64 = 5.0; //seconds without an answer before a guessed transfer is undone
+const MARKER: [f32; 4] = [1.0, 1.0, 0.0, 0.6];
+
+//fieldless, so the cast is the inverse of EquipType::from_idx
+pub fn equip_slot(e: EquipType) -> usize {
+	e as usize
+}
+
+pub type Snapshot = (Vec<Unit>, Option<(Vec<Unit>, Vec<Equipment>)>, Vec<Equipment>);
+
+//everything a transfer can touch, the storages only exist in the safe zone
+pub struct Inventory<'a> {
+	pub team: &'a mut Vec<Unit>,
+	pub storage: Option<(&'a mut Vec<Unit>, &'a mut Vec<Equipment>)>,
+	pub equipment: &'a mut Vec<Equipment>,
+}
+
+enum Item {
+	Unit(Unit),
+	Equipment(Equipment),
+}
+
+impl<'a> Inventory<'a> {
+	pub fn snapshot(&self) -> Snapshot {
+		(self.team.clone(), self.storage.as_ref().map(|(us, es)| (us.to_vec(), es.to_vec())), self.equipment.clone())
+	}
+
+	pub fn restore(&mut self, s: Snapshot) {
+		*self.team = s.0;
+		if let (Some((us, es)), Some((us2, es2))) = (self.storage.as_mut(), s.1) {
+			**us = us2;
+			**es = es2;
+		}
+		*self.equipment = s.2;
+	}
+
+	fn unit_list(&mut self, in_team: bool) -> Option<&mut Vec<Unit>> {
+		if in_team { Some(&mut *self.team) } else { self.storage.as_mut().map(|(us, _)| &mut **us) }
+	}
+
+	fn equipment_list(&mut self, safe: bool) -> Option<&mut Vec<Equipment>> {
+		if safe { self.storage.as_mut().map(|(_, es)| &mut **es) } else { Some(&mut *self.equipment) }
+	}
+
+	fn slot(&mut self, in_team: bool, uidx: usize, eidx: EquipType) -> Option<&mut Option<Equipment>> {
+		self.unit_list(in_team)?.get_mut(uidx)?.equipment.get_mut(equip_slot(eidx))
+	}
+
+	fn take(&mut self, i: &InventoryType) -> Option<Item> {
+		use InventoryType::*;
+		match *i {
+			Team(i) if i < self.team.len() => Some(Item::Unit(self.team.remove(i))),
+			Team(_) => None,
+			UnitStorage(i) => self.unit_list(false).filter(|us| i < us.len()).map(|us| Item::Unit(us.remove(i))),
+			EquipmentStorage(safe, i) => self.equipment_list(safe).filter(|es| i < es.len()).map(|es| Item::Equipment(es.remove(i))),
+			Unit{ in_team, uidx, eidx } => self.slot(in_team, uidx, eidx).and_then(|s| s.take()).map(Item::Equipment),
+		}
+	}
+
+	//hands back whatever was already where the item went, if anything
+	fn put(&mut self, i: &InventoryType, item: Item) -> Result<Option<Item>, Item> {
+		use InventoryType::*;
+		match (i, item) {
+			(&Team(j), Item::Unit(u)) => {
+				let j = j.min(self.team.len());
+				self.team.insert(j, u);
+				Ok(None)
+			},
+			(&UnitStorage(_), Item::Unit(u)) => match self.unit_list(false) {
+				Some(us) => { us.push(u); Ok(None) },
+				None => Err(Item::Unit(u)),
+			},
+			(&EquipmentStorage(safe, _), Item::Equipment(e)) => match self.equipment_list(safe) {
+				Some(es) => { es.push(e); Ok(None) },
+				None => Err(Item::Equipment(e)),
+			},
+			(&Unit{ in_team, uidx, eidx }, Item::Equipment(e)) => match self.slot(in_team, uidx, eidx) {
+				Some(s) => Ok(std::mem::replace(s, Some(e)).map(Item::Equipment)),
+				None => Err(Item::Equipment(e)),
+			},
+			(_, item) => Err(item),
+		}
+	}
+
+	//a guess at what the server will do with a transfer, returns false and leaves things alone if it can't be made sense of
+	pub fn transfer(&mut self, from: &InventoryType, to: &InventoryType) -> bool {
+		let item = match self.take(from) {
+			Some(item) => item,
+			None => return false,
+		};
+		match self.put(to, item) {
+			Ok(None) => true,
+			Ok(Some(displaced)) => {
+				//swaps put the old item where the new one came from
+				if let Err(_) = self.put(from, displaced) {
+					log!("lost track of a displaced item");
+				}
+				true
+			},
+			Err(item) => {
+				let _ = self.put(from, item);
+				false
+			},
+		}
+	}
+}
+
+//units and equipment can't be compared directly, but their encodings can
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+	serialize(a).ok() == serialize(b).ok()
+}
+
+//everything the server has been sent that it answers by sending the team back, oldest first
+//there's no id in the answers, so they're matched up with these in the order they were sent
+enum Request {
+	Transfer{ from: InventoryType, to: InventoryType, guessed: bool },
+	Other, //heals, purchases, perk choices and repairs, which only move the confirmed state along
+}
+
+pub enum Answer {
+	Accepted,
+	Refused(InventoryType, InventoryType),
+	Other,
+}
+
+//transfers that have been shown locally but not confirmed by the server yet
+pub struct PendingTransfers {
+	requests: Vec<Request>,
+	confirmed: Option<Snapshot>, //the state with only the answered transfers in it, to rebuild from and fall back on
+	age: f64,
+}
+
+impl PendingTransfers {
+	pub fn new() -> Self {
+		Self { requests: Vec::new(), confirmed: None, age: 0.0 }
+	}
+
+	//a fresh safe zone replaces everything, whatever was still waiting went with it
+	pub fn reset(&mut self) {
+		self.requests.clear();
+		self.confirmed = None;
+		self.age = 0.0;
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.requests.is_empty()
+	}
+
+	//where the guessed transfers have put things, for marking them as unconfirmed
+	pub fn destinations(&self) -> impl Iterator<Item = &InventoryType> {
+		self.requests.iter().filter_map(|r| match r {
+			Request::Transfer{ to, guessed: true, .. } => Some(to),
+			_ => None,
+		})
+	}
+
+	fn has_transfers(&self) -> bool {
+		self.requests.iter().any(|r| if let Request::Transfer{ .. } = r { true } else { false })
+	}
+
+	//shows the transfer straight away, returns false if it couldn't be guessed and has to wait for the server
+	pub fn apply(&mut self, mut inventory: Inventory, from: InventoryType, to: InventoryType) -> bool {
+		if self.confirmed.is_none() {
+			self.confirmed = Some(inventory.snapshot());
+		}
+		if self.requests.is_empty() {
+			self.age = 0.0;
+		}
+		let guessed = inventory.transfer(&from, &to);
+		self.requests.push(Request::Transfer{ from, to, guessed });
+		guessed
+	}
+
+	//for anything else the server answers with the team, so the answers after it still line up
+	pub fn other(&mut self) {
+		if self.requests.is_empty() {
+			self.age = 0.0;
+		}
+		self.requests.push(Request::Other);
+	}
+
+	//the server's team and equipment are already in the inventory, but the storage still has every guess in it
+	//so it's rebuilt from the confirmed one, with the oldest transfer added only if the server's answer matches it
+	//a refused transfer is dropped rather than put back on top, and everything after it is guessed again without it
+	//transfers inside the safe zone storage leave the team and equipment alone, so a refusal of one looks the same as it being done
+	//those are taken as done and the next SafeZoneInfo puts the storage right if they weren't
+	pub fn reconcile(&mut self, mut inventory: Inventory) -> Option<Answer> {
+		if self.requests.is_empty() { return None; }
+		let request = self.requests.remove(0);
+		self.age = 0.0;
+		let (team, storage, equipment) = match self.confirmed.take() {
+			Some(c) => c,
+			None => return Some(Answer::Other),
+		};
+		let (storage, answer) = match request {
+			Request::Transfer{ from, to, guessed: true } => {
+				let mut expected = (team, storage.clone(), equipment);
+				Inventory{ team: &mut expected.0, storage: expected.1.as_mut().map(|(us, es)| (us, es)), equipment: &mut expected.2 }.transfer(&from, &to);
+				if same(&expected.0, &*inventory.team) && same(&expected.2, &*inventory.equipment) {
+					(expected.1, Answer::Accepted)
+				} else {
+					(storage, Answer::Refused(from, to))
+				}
+			},
+			_ => (storage, Answer::Other),
+		};
+		let confirmed = (inventory.team.clone(), storage, inventory.equipment.clone());
+		inventory.restore(confirmed.clone());
+		for r in self.requests.iter_mut() {
+			if let Request::Transfer{ from, to, guessed } = r {
+				*guessed = inventory.transfer(from, to);
+			}
+		}
+		if self.has_transfers() {
+			self.confirmed = Some(confirmed);
+		}
+		Some(answer)
+	}
+
+	//the state to roll back to when the server has gone quiet for long enough that the guesses should be thrown away
+	pub fn expired(&mut self, dt: f64) -> Option<Snapshot> {
+		if self.requests.is_empty() { return None; }
+		self.age += dt;
+		if self.age > PENDING_TIMEOUT {
+			self.requests.clear();
+			self.confirmed.take()
+		} else {
+			None
+		}
+	}
+
+	pub fn draw_marker(&self, v2: &mut Vec<Vertex>, pos: Vec2<f32>, size: Vec2<f32>) {
+		let s = size.x.min(size.y) * 0.25;
+		quad(v2, (pos + size - vec2(s, s)).extend(13.0), vec2(s, s), Color(MARKER));
+	}
+}
+
+#[cfg(test)]
+pub mod tests {
+	use super::*;
+	use serde::de::DeserializeOwned;
+
+	//lib's types have no constructors, but bincode reads all zeroes as empty lists, zero numbers and first variants
+	pub fn blank<T: DeserializeOwned>() -> T {
+		deserialize(&[0; 4096][..]).unwrap()
+	}
+
+	//units are told apart by hp, nothing here changes it
+	pub fn unit(hp: f64) -> Unit {
+		let mut u: Unit = blank();
+		u.hp = hp;
+		u
+	}
+
+	pub fn hps(units: &[Unit]) -> Vec<f64> {
+		units.iter().map(|u| u.hp).collect()
+	}
+
+	struct State {
+		team: Vec<Unit>,
+		unit_storage: Vec<Unit>,
+		equipment_storage: Vec<Equipment>,
+		equipment: Vec<Equipment>,
+	}
+
+	impl State {
+		fn new(team: &[f64]) -> Self {
+			Self { team: team.iter().cloned().map(unit).collect(), unit_storage: Vec::new(), equipment_storage: Vec::new(), equipment: Vec::new() }
+		}
+
+		fn inventory(&mut self) -> Inventory {
+			Inventory{ team: &mut self.team, storage: Some((&mut self.unit_storage, &mut self.equipment_storage)), equipment: &mut self.equipment }
+		}
+
+		//the server only sends the team and equipment back, the storage keeps whatever the client has
+		fn answer(&mut self, p: &mut PendingTransfers, team: &[f64]) -> &'static str {
+			self.team = team.iter().cloned().map(unit).collect();
+			match p.reconcile(self.inventory()) {
+				Some(Answer::Accepted) => "accepted",
+				Some(Answer::Refused(..)) => "refused",
+				Some(Answer::Other) => "other",
+				None => "none",
+			}
+		}
+	}
+
+	#[test]
+	fn accepted_transfers_are_kept() {
+		let (mut s, mut p) = (State::new(&[1.0, 2.0]), PendingTransfers::new());
+		assert!(p.apply(s.inventory(), InventoryType::Team(0), InventoryType::Team(1)));
+		assert_eq!(hps(&s.team), vec!(2.0, 1.0));
+		assert_eq!(s.answer(&mut p, &[2.0, 1.0]), "accepted");
+		assert_eq!(hps(&s.team), vec!(2.0, 1.0));
+		assert!(p.is_empty());
+	}
+
+	#[test]
+	fn refused_transfers_are_dropped() {
+		let (mut s, mut p) = (State::new(&[1.0, 2.0]), PendingTransfers::new());
+		p.apply(s.inventory(), InventoryType::Team(0), InventoryType::UnitStorage(0));
+		assert_eq!((hps(&s.team), hps(&s.unit_storage)), (vec!(2.0), vec!(1.0)));
+		assert_eq!(s.answer(&mut p, &[1.0, 2.0]), "refused");
+		assert_eq!((hps(&s.team), hps(&s.unit_storage)), (vec!(1.0, 2.0), vec!()));
+		assert!(p.is_empty());
+		assert!(p.expired(PENDING_TIMEOUT * 2.0).is_none());
+	}
+
+	#[test]
+	fn later_transfers_are_guessed_again_without_a_refused_one() {
+		let (mut s, mut p) = (State::new(&[1.0, 2.0, 3.0]), PendingTransfers::new());
+		p.apply(s.inventory(), InventoryType::Team(0), InventoryType::UnitStorage(0));
+		p.apply(s.inventory(), InventoryType::Team(0), InventoryType::UnitStorage(0));
+		assert_eq!((hps(&s.team), hps(&s.unit_storage)), (vec!(3.0), vec!(1.0, 2.0)));
+		assert_eq!(s.answer(&mut p, &[1.0, 2.0, 3.0]), "refused");
+		assert_eq!((hps(&s.team), hps(&s.unit_storage)), (vec!(2.0, 3.0), vec!(1.0)));
+		assert_eq!(s.answer(&mut p, &[2.0, 3.0]), "accepted");
+		assert_eq!((hps(&s.team), hps(&s.unit_storage)), (vec!(2.0, 3.0), vec!(1.0)));
+		assert!(p.is_empty());
+	}
+
+	#[test]
+	fn answers_to_other_requests_keep_the_guesses_after_them() {
+		let (mut s, mut p) = (State::new(&[1.0, 2.0]), PendingTransfers::new());
+		p.other();
+		p.apply(s.inventory(), InventoryType::Team(0), InventoryType::Team(1));
+		assert_eq!(s.answer(&mut p, &[1.0, 2.0]), "other");
+		assert_eq!(hps(&s.team), vec!(2.0, 1.0));
+		assert!(!p.is_empty());
+		assert_eq!(s.answer(&mut p, &[2.0, 1.0]), "accepted");
+		assert_eq!(hps(&s.team), vec!(2.0, 1.0));
+		assert!(p.is_empty());
+		assert_eq!(s.answer(&mut p, &[2.0, 1.0]), "none");
+	}
+
+	#[test]
+	fn unanswered_transfers_expire_back_to_the_confirmed_state() {
+		let (mut s, mut p) = (State::new(&[1.0, 2.0]), PendingTransfers::new());
+		p.apply(s.inventory(), InventoryType::Team(0), InventoryType::UnitStorage(0));
+		assert!(p.expired(PENDING_TIMEOUT * 0.5).is_none());
+		let confirmed = p.expired(PENDING_TIMEOUT).unwrap();
+		s.inventory().restore(confirmed);
+		assert_eq!((hps(&s.team), hps(&s.unit_storage)), (vec!(1.0, 2.0), vec!()));
+		assert!(p.is_empty());
+	}
+}
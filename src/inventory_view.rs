@@ -0,0 +1,191 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::inventory::equip_slot;
+
+const EQUIPMENT_SORTS: [SortKey; 5] = [SortKey::Server, SortKey::Stat, SortKey::Durability, SortKey::Repair, SortKey::Type];
+const UNIT_SORTS: [SortKey; 6] = [SortKey::Server, SortKey::Hp, SortKey::Attack, SortKey::Armor, SortKey::Block, SortKey::Regen];
+const CLASSES: [&str; 3] = ["any", "melee", "ranged"];
+const ELEMENTS: [&str; 4] = ["any", "red", "green", "blue"];
+const MAX_SEARCH: usize = 24;
+
+pub const CONTROL_SIZE: Vec2<f32> = Vec2{ x: 0.2, y: 0.06 };
+const DIM: [f32; 4] = [0.0, 0.0, 0.0, 0.7];
+
+fn class_index(c: Class) -> usize {
+	match c { Class::Melee => 1, Class::Ranged => 2 }
+}
+
+fn element_index(e: Element) -> usize {
+	match e { Element::Red => 1, Element::Green => 2, Element::Blue => 3 }
+}
+
+fn cmp(a: f64, b: f64) -> std::cmp::Ordering {
+	a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum SortKey {
+	Server,
+	Stat,
+	Durability,
+	Repair,
+	Type,
+	Hp,
+	Attack,
+	Armor,
+	Block,
+	Regen,
+}
+
+impl SortKey {
+	pub fn label(self) -> &'static str {
+		match self {
+			SortKey::Server => "server",
+			SortKey::Stat => "stat",
+			SortKey::Durability => "durability",
+			SortKey::Repair => "repair",
+			SortKey::Type => "type",
+			SortKey::Hp => "hp",
+			SortKey::Attack => "attack",
+			SortKey::Armor => "armor",
+			SortKey::Block => "block",
+			SortKey::Regen => "regen",
+		}
+	}
+}
+
+#[derive(Clone,Copy)]
+enum Control {
+	Sort,
+	Search,
+	Class,
+	Element,
+}
+
+//a way of looking at one of the storage grids, the vectors themselves stay in server order so transfers still use the real indices
+pub struct InventoryView {
+	units: bool,
+	pub sort: SortKey,
+	pub search: String,
+	pub searching: bool,
+	pub class: usize,
+	pub element: usize,
+}
+
+impl InventoryView {
+	pub fn equipment() -> Self {
+		Self { units: false, sort: SortKey::Server, search: String::new(), searching: false, class: 0, element: 0 }
+	}
+
+	pub fn units() -> Self {
+		Self { units: true, ..Self::equipment() }
+	}
+
+	//the real index of whatever gets drawn in each grid position
+	pub fn equipment_order(&self, e: &[Equipment]) -> Vec<usize> {
+		let mut order: Vec<usize> = (0..e.len()).collect();
+		match self.sort {
+			SortKey::Stat => order.sort_by(|&a, &b| cmp(e[b].stat1.0, e[a].stat1.0)),
+			SortKey::Durability => order.sort_by(|&a, &b| cmp(e[a].durability, e[b].durability)),
+			SortKey::Repair => order.sort_by(|&a, &b| cmp(e[b].repair_cost, e[a].repair_cost)),
+			SortKey::Type => order.sort_by(|&a, &b| equip_slot(e[a].equip_type).cmp(&equip_slot(e[b].equip_type)).then(cmp(e[b].stat1.0, e[a].stat1.0))),
+			_ => {},
+		}
+		order
+	}
+
+	pub fn unit_order(&self, u: &[Unit]) -> Vec<usize> {
+		let mut order: Vec<usize> = (0..u.len()).collect();
+		let key = |u: &Unit| match self.sort {
+			SortKey::Hp => u.max_hp,
+			SortKey::Attack => u.attack,
+			SortKey::Armor => u.armor,
+			SortKey::Block => u.block,
+			SortKey::Regen => u.regen,
+			_ => 0.0,
+		};
+		if self.sort != SortKey::Server {
+			order.sort_by(|&a, &b| cmp(key(&u[b]), key(&u[a])));
+		}
+		order
+	}
+
+	fn search_matches(&self, text: &str) -> bool {
+		self.search.is_empty() || text.to_lowercase().contains(&self.search.to_lowercase())
+	}
+
+	pub fn equipment_matches(&self, e: &Equipment) -> bool {
+		let text = format!("{} {} {} {} {}",e.desc,e.stat_name1(),e.stat_name2(),e.stat_name1_secondary().unwrap_or_default(),e.stat_name2_secondary().unwrap_or_default());
+		self.search_matches(&text)
+	}
+
+	pub fn unit_matches(&self, u: &Unit) -> bool {
+		let perks: Vec<&str> = u.perks.iter().map(|p| p.desc.as_str()).collect();
+		(self.class == 0 || self.class == class_index(u.class))
+			&& (self.element == 0 || self.element == element_index(u.element))
+			&& self.search_matches(&format!("{} {} {}",CLASSES[class_index(u.class)],ELEMENTS[element_index(u.element)],perks.join(" ")))
+	}
+
+	fn controls(&self, pos: Vec2<f32>) -> Vec<(Button, Control)> {
+		let button = |i: usize, name: String| Button {
+			name,
+			pos: pos + vec2(CONTROL_SIZE.x * 1.05 * i as f32, 0.0),
+			size: CONTROL_SIZE,
+			tex: Color(if self.searching && i == 1 { CYAN } else { DARK_GREY }),
+			edge: None,
+		};
+		let search = if self.searching { format!("{}_",self.search) } else if self.search.is_empty() { "search".to_string() } else { self.search.clone() };
+		let mut controls = vec!(
+			(button(0, format!("sort: {}",self.sort.label())), Control::Sort),
+			(button(1, search), Control::Search),
+		);
+		if self.units {
+			controls.push((button(2, format!("class: {}",CLASSES[self.class])), Control::Class));
+			controls.push((button(3, format!("element: {}",ELEMENTS[self.element])), Control::Element));
+		}
+		controls
+	}
+
+	//returns whether one of the controls was clicked, clicking anywhere else stops the search from taking keys
+	pub fn click(&mut self, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>) -> bool {
+		let c = self.controls(pos).into_iter().find(|(b, _)| b.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some()).map(|(_, c)| c);
+		self.searching = false;
+		match c {
+			Some(Control::Sort) => {
+				let sorts: &[SortKey] = if self.units { &UNIT_SORTS } else { &EQUIPMENT_SORTS };
+				let i = sorts.iter().position(|&s| s == self.sort).map(|i| i + 1).unwrap_or(0);
+				self.sort = sorts[i % sorts.len()];
+			},
+			Some(Control::Search) => self.searching = true,
+			Some(Control::Class) => self.class = (self.class + 1) % CLASSES.len(),
+			Some(Control::Element) => self.element = (self.element + 1) % ELEMENTS.len(),
+			None => return false,
+		}
+		true
+	}
+
+	//returns whether the key was used by the search
+	pub fn key(&mut self, key: &str) -> bool {
+		if !self.searching { return false; }
+		match key {
+			"Backspace" => { self.search.pop(); },
+			"Escape" => { self.search.clear(); self.searching = false; },
+			"Enter" => self.searching = false,
+			k if k.chars().count() == 1 && self.search.len() < MAX_SEARCH => self.search.push_str(k),
+			_ => {},
+		}
+		true
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>) {
+		for (b, _) in self.controls(pos) {
+			b.draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+		}
+	}
+
+	//covers things that don't match, so they're still there to drag around but stand out less
+	pub fn dim(&self, v2: &mut Vec<Vertex>, pos: Vec2<f32>, size: Vec2<f32>) {
+		quad(v2, pos.extend(11.0), size, Color(DIM));
+	}
+}
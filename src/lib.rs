@@ -1,7 +1,6 @@
-#![feature(const_transmute)]
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{MouseEvent,WebSocket,ErrorEvent,HtmlInputElement,MessageEvent,KeyboardEvent};
+use web_sys::{MouseEvent,PointerEvent,WheelEvent,WebSocket,ErrorEvent,HtmlInputElement,MessageEvent,KeyboardEvent};
 use std::cell::Cell;
 
 macro_rules! log {
@@ -19,12 +18,54 @@ use boiler_plate::*;
 mod thing;
 use thing::*;
 mod collision;
+mod viewport;
+mod input;
+use input::*;
+mod replay;
+use replay::*;
+mod animation;
+use animation::*;
+mod combat_log;
+mod storage;
+mod replay_library;
+use replay_library::*;
+mod summary;
+use summary::*;
+mod heuristics;
+mod advisor;
+use advisor::Advice;
+mod simulator;
+use simulator::simulate;
+mod presets;
+use presets::*;
+mod inventory_view;
+use inventory_view::*;
+mod bulk;
+use bulk::*;
+mod cost;
+mod inventory;
+use inventory::*;
+mod history;
+use history::*;
+mod dive_map;
+use dive_map::*;
+mod stats;
+use stats::*;
+mod scouting;
+use scouting::*;
+mod move_picker;
+use move_picker::*;
+mod dialog;
+use dialog::*;
+mod toasts;
+use toasts::*;
+mod settings;
+use settings::*;
 
 const DEFAULT_IP: &str = "192.168.1.55";
-const MESSAGE_DURATION: f32 = 30.0;
-const FRAMES_PER_SNAPSHOT: usize = 1;
 
 const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.06, y: 0.06 };
+const MAX_FRAME_TIME: f64 = 0.1; //so coming back to a backgrounded tab doesn't skip the rest of a fight or time out everything waiting on the server
 
 #[derive(Debug,Clone)]
 enum State {
@@ -35,6 +76,8 @@ enum State {
 }
 use State::*;
 
+type Selected = Option<(Box<dyn Thing<Args=bool>>, Option<InventoryType>)>;
+
 #[allow(unused)]
 impl State {
 	fn is_safe_zone(&self) -> bool { if let SafeZone(_,_) = self { true } else { false } }
@@ -50,9 +93,11 @@ pub fn start() -> Result<(), JsValue> {
 	
 	let document = window().document().expect(l!());
 	let canvas = Rc::new(document.get_element_by_id("canvas").expect(l!()).dyn_into::<web_sys::HtmlCanvasElement>()?);
-	let context = setup_rendering(&canvas, &document)?;
-	let ip = window().location().href().map_err(|_| ()).and_then(|s| {
-		let mut s = s.split('?'); s.next();	s.next().and_then(|s| s.find("ip=").map(|i| {	let s = s.split_at(i+3).1; s.split('&').next().expect(l!()).to_string()	})).ok_or(())	}).unwrap_or(DEFAULT_IP.to_string());
+	let settings = Rc::new(RefCell::new(Settings::load()));
+	let viewport = Rc::new(Cell::new(Viewport::new(vec2(1.0, 1.0), 1.0, settings.borrow().prefs.ui_scale)));
+	let context = setup_rendering(&canvas, viewport.clone())?;
+	let vp = viewport.get();
+	let ip = query_param("ip").unwrap_or(DEFAULT_IP.to_string());
 	log!("1");
 	let ws = WebSocket::new(&format!("wss://{}:2794",ip))?;
 	log!("2");
@@ -63,10 +108,26 @@ pub fn start() -> Result<(), JsValue> {
 	let repair_button_selected = Rc::new(Cell::new(false));
 	let repair_target = Rc::new(Cell::new(5f64));
 	let equipment: Rc<RefCell<Vec<Equipment>>> = Rc::new(RefCell::new(Vec::new()));
-	let recording: Rc<RefCell<Option<(FightRecording, usize, bool)>>> = Rc::new(RefCell::new(None));
+	let recording: Rc<RefCell<Option<Replay>>> = Rc::new(RefCell::new(None));
+	let library = Rc::new(RefCell::new(ReplayLibrary::load()));
+	let presets = Rc::new(RefCell::new(Presets::load()));
+	let equipment_view = Rc::new(RefCell::new(InventoryView::equipment()));
+	let unit_view = Rc::new(RefCell::new(InventoryView::units()));
+	let bulk = Rc::new(RefCell::new(BulkSelection::new()));
+	let pending = Rc::new(RefCell::new(PendingTransfers::new()));
+	let history = Rc::new(RefCell::new(TransferHistory::new()));
+	let dive_map = Rc::new(RefCell::new(DiveMap::new()));
+	let stats = Rc::new(RefCell::new(Stats::load()));
+	let scouting = Rc::new(RefCell::new(Scouting::load()));
+	let move_picker = Rc::new(RefCell::new(MovePicker::new()));
+	let confirmations = Rc::new(RefCell::new(Confirmations::load()));
+	let summary: Rc<RefCell<Option<FightSummary>>> = Rc::new(RefCell::new(None));
+	let advice: Rc<RefCell<Option<Advice>>> = Rc::new(RefCell::new(None));
 	let state: Rc<RefCell<State>> = Rc::new(RefCell::new(InQueue));
-	let selected: Rc<RefCell<Option<(Box<dyn Thing<Args=bool>>, Option<InventoryType>)>>> = Rc::new(RefCell::new(None));
-	let messages: Rc<RefCell<Vec<(String, f32)>>> = Rc::new(RefCell::new(Vec::new()));
+	let selected: Rc<RefCell<Selected>> = Rc::new(RefCell::new(None));
+	let messages = Rc::new(RefCell::new(Toasts::new()));
+	messages.borrow_mut().duration = settings.borrow().prefs.toast_duration;
+	viewport.set(Viewport { display: settings.borrow().display(), ..viewport.get() });
 	let move_options: Rc<RefCell<Vec<MoveOption>>> = Rc::new(RefCell::new(Vec::new()));
 	let team: Rc<RefCell<Vec<Unit>>> = Rc::new(RefCell::new(Vec::new()));
 	let opponent: Rc<RefCell<Vec<UnitView>>> = Rc::new(RefCell::new(Vec::new()));
@@ -108,8 +169,8 @@ pub fn start() -> Result<(), JsValue> {
 		edge: None,
 	});
 	let purchase_unit_button = Rc::new(Button {
-		name: format!("purchase unit: {:.2}",UNIT_COST),
-		pos: -BUTTON_SIZE * vec2(1.25, 0.5) + vec2(0.0, bottom_edge() + 0.07),
+		name: format!("purchase unit: {:.2}",cost::unit_purchase()),
+		pos: -BUTTON_SIZE * vec2(1.25, 0.5) + vec2(0.0, vp.bottom_edge() + 0.07),
 		size: BUTTON_SIZE * vec2(2.5, 1.0),
 		tex: Color(CYAN),
 		edge: None,
@@ -135,6 +196,34 @@ pub fn start() -> Result<(), JsValue> {
 		tex: Color(BLUE),
 		edge: None,
 	});
+	let step_back_button = Rc::new(Button {
+		name: "-1".to_string(),
+		pos: -vec2(BUTTON_SIZE.y, BUTTON_SIZE.y) * 0.5 - vec2(BUTTON_SIZE.y * 2.2, 0.0),
+		size: vec2(BUTTON_SIZE.y, BUTTON_SIZE.y),
+		tex: Color(DARK_GREY),
+		edge: None,
+	});
+	let step_forward_button = Rc::new(Button {
+		name: "+1".to_string(),
+		pos: -vec2(BUTTON_SIZE.y, BUTTON_SIZE.y) * 0.5 + vec2(BUTTON_SIZE.y * 2.2, 0.0),
+		size: vec2(BUTTON_SIZE.y, BUTTON_SIZE.y),
+		tex: Color(DARK_GREY),
+		edge: None,
+	});
+	let slower_button = Rc::new(Button {
+		name: "slower".to_string(),
+		pos: -vec2(BUTTON_SIZE.y, BUTTON_SIZE.y) * 0.5 - vec2(BUTTON_SIZE.y * 3.3, 0.0),
+		size: vec2(BUTTON_SIZE.y, BUTTON_SIZE.y),
+		tex: Color(PURPLE),
+		edge: None,
+	});
+	let faster_button = Rc::new(Button {
+		name: "faster".to_string(),
+		pos: -vec2(BUTTON_SIZE.y, BUTTON_SIZE.y) * 0.5 + vec2(BUTTON_SIZE.y * 3.3, 0.0),
+		size: vec2(BUTTON_SIZE.y, BUTTON_SIZE.y),
+		tex: Color(YELLOW),
+		edge: None,
+	});
 	let heal_all_button = Rc::new(Button {
 		name: "heal all".to_string(),
 		pos: vec2(0.0, -0.02) - BUTTON_SIZE * 0.75,
@@ -145,25 +234,24 @@ pub fn start() -> Result<(), JsValue> {
 	let square_button_size = vec2(BUTTON_SIZE.y, BUTTON_SIZE.y) * 1.5;
 	let repair_button = Rc::new(RefCell::new(Button {
 		name: format!("{:.3}",repair_target.get()),
-		pos: equip_box_pos() + equip_box_size() - vec2(square_button_size.x * 2.0 + 0.02, -0.02),
+		pos: equip_box_pos(&vp) + equip_box_size() - vec2(square_button_size.x * 2.0 + 0.02, -0.02),
 		size: square_button_size,
 		tex: Color(CYAN),
 		edge: None,
-	}.edgeified(false)));
+	}.edgeified(&vp, false)));
 	let juice_button = Rc::new(Button {
 		name: "juice".to_string(),
-		pos: equip_box_pos() + equip_box_size() - vec2(square_button_size.x, -0.02),
+		pos: equip_box_pos(&vp) + equip_box_size() - vec2(square_button_size.x, -0.02),
 		size: square_button_size,
 		tex: Color(RED),
 		edge: None,
-	}.edgeified(false));
+	}.edgeified(&vp, false));
 	
 	let next_click = Rc::new(Cell::new(false));
 	let next_click2 = next_click.clone();
 	let repair_button_selected2 = repair_button_selected.clone();
 	let repair_target2 = repair_target.clone();
 	let repair_button2 = repair_button.clone();
-	let canvas2 = canvas.clone();
 	let fight_button2 = fight_button.clone();
 	let do_not_button2 = do_not_button.clone();
 	let up_button2 = up_button.clone();
@@ -172,6 +260,10 @@ pub fn start() -> Result<(), JsValue> {
 	let pause_button2 = pause_button.clone();
 	let rewind_button2 = rewind_button.clone();
 	let skip_button2 = skip_button.clone();
+	let step_back_button2 = step_back_button.clone();
+	let step_forward_button2 = step_forward_button.clone();
+	let slower_button2 = slower_button.clone();
+	let faster_button2 = faster_button.clone();
 	let move_options2 = move_options.clone();
 	let heal_all_button2 = heal_all_button.clone();
 	let team2 = team.clone();
@@ -183,44 +275,108 @@ pub fn start() -> Result<(), JsValue> {
 	let recording2 = recording.clone();
 	let depth2 = depth.clone();
 	let gold2 = gold.clone();
+	let viewport2 = viewport.clone();
+	let library2 = library.clone();
+	let presets2 = presets.clone();
+	let equipment_view2 = equipment_view.clone();
+	let unit_view2 = unit_view.clone();
+	let bulk2 = bulk.clone();
+	let equipment2 = equipment.clone();
+	let opponent_name2 = opponent_name.clone();
+	let messages2 = messages.clone();
+	let summary2 = summary.clone();
+	let pending2 = pending.clone();
+	let history2 = history.clone();
+	let dive_map2 = dive_map.clone();
+	let stats2 = stats.clone();
+	let scouting2 = scouting.clone();
+	let move_picker2 = move_picker.clone();
+	let confirmations2 = confirmations.clone();
+	let settings2 = settings.clone();
+	let context2 = context.clone();
+	let canvas2 = canvas.clone();
 	let onclick = Closure::wrap(Box::new(move|e: MouseEvent| {
+		let vp = viewport2.get();
 		repair_button_selected2.set(false);
 		repair_button2.borrow_mut().tex = Color(CYAN);
 		repair_button2.borrow_mut().name = format!("{:.3}",repair_target2.get());
 		if next_click2.replace(true) {
-			let m = screen_coords(e.client_x(), e.client_y(), &canvas2);
+			let m = vp.screen_coords(e.client_x(), e.client_y());
 			let mut selected = selected2.borrow_mut();
 			let mut clicked = false;
 			let mut state = state2.borrow_mut();
 			let mut recording = recording2.borrow_mut();
-			if recording.is_some() && skip_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+			//the settings panel is drawn over the message log, so it gets the click first
+			let skip = confirmations2.borrow().skip.clone();
+			let settings_action = if confirmations2.borrow().is_open() { None } else { settings2.borrow_mut().click(&vp, m, &skip) };
+			if let Some(action) = settings_action {
+				clicked = true;
+				match action {
+					SettingsAction::Changed => {
+						messages2.borrow_mut().duration = settings2.borrow().prefs.toast_duration;
+						let settings = settings2.borrow();
+						let vp = Viewport { display: settings.display(), ..Viewport::new(vp.size, vp.dpr, settings.prefs.ui_scale) };
+						viewport2.set(vp);
+						apply_viewport(&context2, &canvas2, &vp);
+					},
+					SettingsAction::Confirm(kind) => confirmations2.borrow_mut().set_asking(kind, skip.contains(&kind)),
+					SettingsAction::Used => {},
+				}
+			} else if !confirmations2.borrow().is_open() && messages2.borrow_mut().click(&vp, m) {
+				clicked = true;
+			} else if confirmations2.borrow().is_open() {
+				//the dialog is modal, nothing under it can be clicked until it's answered
+				clicked = true;
+				let result = confirmations2.borrow_mut().click(&vp, m);
+				if let Some(DialogResult::Confirm(kind, packets)) = result {
+					send_confirmed(&ws2, &pending2, kind, packets, &mut state, &gold2, &depth2, &move_options2);
+				}
+			} else if recording.is_some() && skip_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 				clicked = true;
 				*recording = None;
 			} else if let Some(ref mut r) = &mut*recording {
-				if pause_button2.borrow().collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+				if pause_button2.borrow().collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 					clicked = true;
-					r.2 = !r.2;
-					pause_button2.borrow_mut().tex = Color(if r.2 {
-						DARK_GREEN
-					} else {
-						GREEN
-					});
-				} else if rewind_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+					r.paused = !r.paused;
+				} else if rewind_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+					clicked = true;
+					r.seek(0.0);
+				} else if step_back_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+					clicked = true;
+					r.step(false);
+				} else if step_forward_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+					clicked = true;
+					r.step(true);
+				} else if slower_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+					clicked = true;
+					r.slower();
+				} else if faster_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+					clicked = true;
+					r.faster();
+				} else if r.timeline_collides(m) {
 					clicked = true;
-					r.1 = 0;
+					r.seek_to_timeline(m);
+				} else if let Some(step) = r.log.clicked(&vp, m) {
+					clicked = true;
+					r.seek(step as f64);
 				}
-				let (t, o) = &r.0.get(r.1);
+				pause_button2.borrow_mut().tex = Color(if r.paused {
+					DARK_GREEN
+				} else {
+					GREEN
+				});
+				let (t, o) = &r.recording.get(r.snapshot());
 				if !t.is_empty() {
 					let size = t[0].size(Vec2::one(), false);
 					for i in 0..t.len() {
-						if let Some(x) = t[i].collides(m, team_unit_pos(t.len(), i, size), Vec2::one(), state.is_safe_zone()) {
+						if let Some(x) = t[i].collides(&vp, m, team_unit_pos(t.len(), i, size), Vec2::one(), state.is_safe_zone()) {
 							clicked = true;
 							if x == 0 {
 								*selected = t[i].select(false).map(|s| (s, Some(InventoryType::Team(i))));
 							} else if x == 1 {
-								send(&ws2, ClientPacket::Purchase(i+1)).expect(l!());
+								send_answered(&ws2, &pending2, ClientPacket::Purchase(i+1));
 							} else {
-								send(&ws2, ClientPacket::PerkChoice(i, x - 2)).expect(l!());
+								send_answered(&ws2, &pending2, ClientPacket::PerkChoice(i, x - 2));
 							}
 							break;
 						}
@@ -229,79 +385,226 @@ pub fn start() -> Result<(), JsValue> {
 				if !o.is_empty() {
 					let size = o[0].size(Vec2::one(), false);
 					for i in 0..o.len() {
-						if o[i].collides(m, opponent_unit_pos(o.len(), i, size), Vec2::one(), false).is_some() {
+						if o[i].collides(&vp, m, opponent_unit_pos(o.len(), i, size), Vec2::one(), false).is_some() {
 							clicked = true;
 							*selected = o[i].select(false).map(|s| (s, None));
 							break;
 						}
 					}
 				}
+			} else if summary2.borrow().is_some() {
+				clicked = true;
+				if summary2.borrow().as_ref().map(|s| s.continue_button().collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some()).unwrap_or(false) {
+					summary2.replace(None);
+				}
+			} else if state.is_safe_zone() && library2.borrow().open {
+				clicked = true;
+				let action = library2.borrow().click(&vp, m);
+				match action {
+					Some(LibraryAction::Watch(i)) => {
+						let mut library = library2.borrow_mut();
+						*recording = Some(settings2.borrow().replay(library.replays[i].recording.clone()));
+						opponent_name2.replace(library.replays[i].opponent);
+						library.open = false;
+					},
+					Some(LibraryAction::Simulate(i)) if library2.borrow().replays[i].recording.snapshots.is_empty() => {
+						messages2.borrow_mut().error("the replay has no fight in it".into());
+					},
+					Some(LibraryAction::Simulate(i)) => {
+						//runs the team as it's currently arranged against the opponent from the start of the saved fight
+						let mut library = library2.borrow_mut();
+						let (_, o) = library.replays[i].recording.get(0);
+						*recording = Some(settings2.borrow().replay(simulate(&team2.borrow(), &o)));
+						//the simulation can only ever be a guess, so say what it leaves out instead of passing it off as the real fight
+						let perks = team2.borrow().iter().map(|u| u.perks.len()).sum::<usize>() + o.iter().map(|o| o.unit.perks.len()).sum::<usize>();
+						messages2.borrow_mut().warning(format!("simulated with guessed combat rules, {} perks ignored",perks));
+						opponent_name2.replace(library.replays[i].opponent);
+						library.open = false;
+					},
+					Some(LibraryAction::Export(i)) => {
+						let library = library2.borrow();
+						let r = &library.replays[i];
+						let exported = serialize(r).map_err(|_| JsValue::NULL).and_then(|data| storage::download(&r.file_name(), &data));
+						if exported.is_err() {
+							messages2.borrow_mut().error("couldn't export the replay".into());
+						}
+					},
+					Some(LibraryAction::Delete(i)) => {
+						let saved = library2.borrow_mut().remove(i);
+						report_saved(saved, &messages2);
+					},
+					Some(LibraryAction::Import) => {
+						let library = library2.clone();
+						let messages = messages2.clone();
+						let picker = storage::upload(FILE_EXTENSION, move|data| {
+							match data.and_then(|data| SavedReplay::import(&data)) {
+								Ok(r) => {
+									let saved = library.borrow_mut().add(r);
+									report_saved(saved, &messages);
+								},
+								Err(e) => messages.borrow_mut().error(e),
+							}
+						});
+						if picker.is_err() {
+							messages2.borrow_mut().error("couldn't open the file picker".into());
+						}
+					},
+					Some(LibraryAction::Close) => library2.borrow_mut().open = false,
+					None => {},
+				}
+			} else if state.is_safe_zone() && stats2.borrow().open {
+				clicked = true;
+				if stats2.borrow().close_button().collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+					stats2.borrow_mut().open = false;
+				}
+			} else if state.is_safe_zone() && presets2.borrow().open {
+				clicked = true;
+				let action = presets2.borrow().click(&vp, m);
+				match action {
+					Some(PresetAction::Apply(i)) => {
+						let mut presets = presets2.borrow_mut();
+						presets.apply(i);
+						presets.open = false;
+					},
+					Some(PresetAction::Delete(i)) => presets2.borrow_mut().remove(i),
+					Some(PresetAction::Save) => {
+						let default = format!("preset {}",presets2.borrow().presets.len() + 1);
+						if let Ok(Some(name)) = window().prompt_with_message_and_default("preset name", &default) {
+							if !name.is_empty() {
+								presets2.borrow_mut().add(name, &team2.borrow());
+							}
+						}
+					},
+					Some(PresetAction::Close) => presets2.borrow_mut().open = false,
+					None => {},
+				}
+			} else if let Some(item) = Some(()).filter(|_| e.shift_key() || e.ctrl_key()).and_then(|_| equipment_at(&vp, m, &equipment2.borrow(), state.storage().map(|(_us, es)| es), &equipment_view2.borrow())) {
+				//shift or ctrl clicking equipment picks it for the bulk actions instead of selecting it
+				clicked = true;
+				bulk2.borrow_mut().toggle(item);
+			} else if let Some(action) = Some(()).filter(|_| state.is_safe_zone()).and_then(|_| bulk2.borrow().click(&vp, m, bulk_panel_pos(&vp))) {
+				clicked = true;
+				let mut bulk = bulk2.borrow_mut();
+				let price = match action {
+					BulkAction::Repair => Some(bulk.repair_cost(&equipment2.borrow(), state.storage().map(|(_us, es)| &es[..]).unwrap_or(&[]), repair_target2.get())),
+					BulkAction::Juice => None,
+					BulkAction::Clear => None,
+				};
+				if !price.map(|p| can_afford(p, gold2.get(), &messages2)).unwrap_or(true) { return; }
+				match action {
+					BulkAction::Repair => for to in bulk.packets() {
+						send_answered(&ws2, &pending2, ClientPacket::Repair(repair_target2.get(), to));
+					},
+					BulkAction::Juice => {
+						let lines = vec!(format!("juice {} items?",bulk.items.len()), "they will be destroyed".to_string());
+						let packets = bulk.packets().into_iter().map(ClientPacket::Juice).collect();
+						let packets = confirmations2.borrow_mut().ask(ConfirmKind::Juice, lines, packets);
+						if let Some(p) = packets {
+							send_confirmed(&ws2, &pending2, ConfirmKind::Juice, p, &mut state, &gold2, &depth2, &move_options2);
+						}
+					},
+					BulkAction::Clear => {},
+				}
+				bulk.clear();
+			} else if (state.is_safe_zone() || state.is_looting()) && !history2.borrow().is_empty() && history2.borrow().button(&vp).collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+				clicked = true;
+				undo_transfer(&ws2, &history2, &pending2, &mut selected, &mut team2.borrow_mut(), &mut state, &mut equipment2.borrow_mut(), &messages2);
 			} else {
 				match &mut*state {
 					SafeZone(unit_storage, _equipment_storage) => {
+						if library2.borrow().button(&vp).collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+							clicked = true;
+							library2.borrow_mut().open = true;
+						}
+						if presets2.borrow().button(&vp).collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+							clicked = true;
+							presets2.borrow_mut().open = true;
+						}
+						if stats2.borrow().button(&vp).collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+							clicked = true;
+							stats2.borrow_mut().open = true;
+						}
 						let mut r = repair_button2.borrow_mut();
-						if r.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+						if r.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 							r.tex = Color([CYAN[0] * 1.1, CYAN[1] * 1.1, CYAN[2] * 1.1, CYAN[3]]);
 							r.name = "".into();
 							repair_button_selected2.set(true);
 						}
+						if equipment_view2.borrow_mut().click(&vp, m, equip_view_pos(&vp)) | unit_view2.borrow_mut().click(&vp, m, unit_view_pos(&vp)) {
+							clicked = true;
+						}
 						if !unit_storage.is_empty() {
 							let size = unit_storage[0].size(storage_unit_scale(), false);
-							for i in 0..unit_storage.len() {
-								if let Some(0) = unit_storage[i].collides(m, storage_unit_pos(i, size) + unit_storage_box_pos(), storage_unit_scale(), false) {
+							for (slot, &i) in unit_view2.borrow().unit_order(unit_storage).iter().enumerate() {
+								if let Some(0) = unit_storage[i].collides(&vp, m, storage_unit_pos(slot, size) + unit_storage_box_pos(&vp), storage_unit_scale(), false) {
 									clicked = true;
 									*selected = unit_storage[i].select(false).map(|s| (s, Some(InventoryType::UnitStorage(i))));
 									break;
 								}
 							}
 						}
-						let mut mo = move_options2.borrow_mut();
-						if !mo.is_empty() {
-							let button_start = vec2(-(4.5 * 0.1 + 5.0), (mo.len() / 10) as f32 * 0.55 + 0.5) * mo[0].size(Vec2::one(), false);
-							let mut p = button_start;
-							let step = mo[0].size(Vec2::one(), false) * 1.1;
-							for i in 0..mo.len() {
-								if mo[i].collides(m, p, Vec2::one(), false).is_some() && team2.borrow().len() <= mo[i].max_group_size.unwrap_or(usize::max_value()) && !team2.borrow().is_empty() {
-									clicked = true;
-									send(&ws2, ClientPacket::Move(i)).expect(l!());
-									gold2.set(0.0);
-									depth2.set(1);
-									*mo = Vec::new();
-									*state = InQueue;
-									break;
+						let action = move_picker2.borrow().click(&vp, m, &move_options2.borrow());
+						match action {
+							Some(MoveAction::Sort) => {
+								clicked = true;
+								move_picker2.borrow_mut().next_sort();
+							},
+							Some(MoveAction::Pick(i)) => {
+								clicked = true;
+								let blocked = move_blocked(&move_options2.borrow()[i], team2.borrow().len());
+								match blocked {
+									Some(reason) => messages2.borrow_mut().warning(reason),
+									None => {
+										let lines = vec!(format!("go to {}?",move_options2.borrow()[i].name.to_lowercase()), "gold and depth will be reset".to_string());
+										let packets = confirmations2.borrow_mut().ask(ConfirmKind::Move, lines, vec!(ClientPacket::Move(i)));
+										if let Some(p) = packets {
+											send_confirmed(&ws2, &pending2, ConfirmKind::Move, p, &mut state, &gold2, &depth2, &move_options2);
+										}
+									},
 								}
-								if (i+1) % 10 == 0 {
-									p.y -= step.y;
-									p.x = button_start.x;
-								} else {
-									p.x += step.x;
+							},
+							_ => {},
+						}
+						if purchase_unit_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+							clicked = true;
+							if can_afford(cost::unit_purchase(), gold2.get(), &messages2) {
+								let lines = vec!(format!("buy a unit for {}?",cost::label(cost::unit_purchase())));
+								let packets = confirmations2.borrow_mut().ask(ConfirmKind::Purchase, lines, vec!(ClientPacket::Purchase(0)));
+								for p in packets.into_iter().flatten() {
+									send_answered(&ws2, &pending2, p);
 								}
 							}
 						}
-						if purchase_unit_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
-								clicked = true;
-							send(&ws2, ClientPacket::Purchase(0)).expect(l!());
-						}
-						if heal_all_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+						if heal_all_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 							clicked = true;
-							for i in 0..team2.borrow().len() {
-								send(&ws2, ClientPacket::Purchase(i+1)).expect(l!());
+							//the server heals whoever the gold covers, so this only stops when nobody can be healed at all
+							let t = team2.borrow();
+							if cost::cheapest_heal(&t).map(|c| can_afford(c, gold2.get(), &messages2)).unwrap_or(false) {
+								if cost::shortfall(cost::heal_all(&t), gold2.get()).is_some() {
+									messages2.borrow_mut().info("not enough gold for everyone, healing who it covers".to_string());
+								}
+								for i in 0..t.len() {
+									send_answered(&ws2, &pending2, ClientPacket::Purchase(i+1));
+								}
 							}
 						}
 					},
 					Looting => {
-						if up_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+						if up_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 							clicked = true;
 							send(&ws2, ClientPacket::Move(0)).expect(l!());
+							dive_map2.borrow_mut().choose(Route::from_move(0));
 							depth2.set(depth2.get() - 1);
 							*state = InQueue;
-						} else if stay_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+						} else if stay_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 							clicked = true;
 							send(&ws2, ClientPacket::Move(1)).expect(l!());
+							dive_map2.borrow_mut().choose(Route::from_move(1));
 							*state = InQueue;
-						} else if down_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+						} else if down_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 							clicked = true;
 							send(&ws2, ClientPacket::Move(2)).expect(l!());
+							dive_map2.borrow_mut().choose(Route::from_move(2));
 							depth2.set(depth2.get() + 1);
 							*state = InQueue;
 						}
@@ -311,28 +614,45 @@ pub fn start() -> Result<(), JsValue> {
 					},
 					InFight(ref mut chosen) => {
 						if !*chosen {
-							if fight_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+							if fight_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 								clicked = true;
-								send(&ws2, ClientPacket::Fight(true)).expect(l!());
-							} else if do_not_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
+								let lines = vec!(format!("fight {}?",opponent_name2.borrow().to_lowercase()), "there's no backing out once it starts".to_string());
+								let packets = confirmations2.borrow_mut().ask(ConfirmKind::Fight, lines, vec!(ClientPacket::Fight(true)));
+								for p in packets.into_iter().flatten() {
+									send(&ws2, p).expect(l!());
+								}
+							} else if do_not_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 								clicked = true;
 								send(&ws2, ClientPacket::Fight(false)).expect(l!());
+								dive_map2.borrow_mut().avoided();
 								*chosen = true;
 							}
 						}
+						if scouting2.borrow().notes_button(&vp).collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+							clicked = true;
+							let name = scouting2.borrow().current_name().map(|n| n.to_string());
+							if let Some(name) = name {
+								let notes = scouting2.borrow().notes(&name);
+								if let Ok(Some(notes)) = window().prompt_with_message_and_default(&format!("notes on {}",name.to_lowercase()), &notes) {
+									scouting2.borrow_mut().set_notes(&name, notes);
+								}
+							}
+						}
 					},
 				}
 				let t = team2.borrow();
 				if !t.is_empty() {
 					for i in 0..t.len() {
-						if let Some(x) = t[i].collides(m, team_unit_pos(t.len(), i, t[0].size(Vec2::one(), false)), Vec2::one(), state.is_safe_zone()) {
+						if let Some(x) = t[i].collides(&vp, m, team_unit_pos(t.len(), i, t[0].size(Vec2::one(), false)), Vec2::one(), state.is_safe_zone()) {
 							clicked = true;
 							if x == 0 {
 								*selected = t[i].select(false).map(|s| (s, Some(InventoryType::Team(i))));
 							} else if x == 1 {
-								send(&ws2, ClientPacket::Purchase(i+1)).expect(l!());
+								if can_afford(cost::heal(&t[i]), gold2.get(), &messages2) {
+									send_answered(&ws2, &pending2, ClientPacket::Purchase(i+1));
+								}
 							} else {
-								send(&ws2, ClientPacket::PerkChoice(i, x - 2)).expect(l!());
+								send_answered(&ws2, &pending2, ClientPacket::PerkChoice(i, x - 2));
 							}
 							break;
 						}
@@ -342,7 +662,7 @@ pub fn start() -> Result<(), JsValue> {
 				if !o.is_empty() {
 					let mut p = vec2((-(o.len() as f32 / 2.0) - 0.1) * o[0].size(Vec2::one(), false).x, 0.3);
 					for i in 0..o.len() {
-						if o[i].collides(m, p, Vec2::one(), false).is_some() {
+						if o[i].collides(&vp, m, p, Vec2::one(), false).is_some() {
 							clicked = true;
 							*selected = o[i].select(false).map(|s| (s, None));
 							break;
@@ -360,63 +680,148 @@ pub fn start() -> Result<(), JsValue> {
 	onclick.forget();
 	
 	let drag_pos = Rc::new(Cell::new(None));
+	let press: Rc<Cell<Option<Press>>> = Rc::new(Cell::new(None));
+	let mouse = Rc::new(Cell::new(Vec2::zero()));
+	let box_select = Rc::new(Cell::new(false));
 	let a = drag_pos.clone();
 	let b = a.clone();
-	let c = a.clone();
 	let canvas2 = canvas.clone();
-	let onmousedown = Closure::wrap(Box::new(move|e: MouseEvent| if e.button() == 0 { a.set(Some(screen_coords(e.client_x(), e.client_y(), &canvas2))) })
-		as Box<dyn Fn(_)>);
-	canvas.set_onmousedown(Some(onmousedown.as_ref().unchecked_ref()));
-	onmousedown.forget();
+	let press2 = press.clone();
+	let mouse2 = mouse.clone();
+	let viewport2 = viewport.clone();
+	let box_select2 = box_select.clone();
+	let onpointerdown = Closure::wrap(Box::new(move|e: PointerEvent| if e.is_primary() && e.button() == 0 {
+		let vp = viewport2.get();
+		let pos = vp.screen_coords(e.client_x(), e.client_y());
+		let touch = e.pointer_type() != "mouse";
+		box_select2.set(e.shift_key() || e.ctrl_key());
+		if !touch { mouse2.set(pos); }
+		a.set(Some(pos));
+		press2.set(Some(Press::new(pos, touch)));
+		let _ = canvas2.set_pointer_capture(e.pointer_id());
+	}) as Box<dyn Fn(_)>);
+	canvas.set_onpointerdown(Some(onpointerdown.as_ref().unchecked_ref()));
+	onpointerdown.forget();
+	
+	let drag_pos2 = drag_pos.clone();
+	let press2 = press.clone();
+	let mouse2 = mouse.clone();
+	let onpointercancel = Closure::wrap(Box::new(move|e: PointerEvent| if e.is_primary() {
+		drag_pos2.set(None);
+		if press2.replace(None).map(|p| p.touch).unwrap_or(false) {
+			mouse2.set(NO_POINTER);
+		}
+	}) as Box<dyn Fn(_)>);
+	canvas.set_onpointercancel(Some(onpointercancel.as_ref().unchecked_ref()));
+	onpointercancel.forget();
+	
+	let recording2 = recording.clone();
+	let viewport2 = viewport.clone();
+	let messages2 = messages.clone();
+	let onwheel = Closure::wrap(Box::new(move|e: WheelEvent| {
+		let vp = viewport2.get();
+		let m = vp.screen_coords(e.client_x(), e.client_y());
+		if messages2.borrow().history_collides(&vp, m) {
+			messages2.borrow_mut().scroll_by(e.delta_y().signum() as isize * 3);
+		} else if let Some(r) = recording2.borrow_mut().as_mut() {
+			if r.log.collides(&vp, m) {
+				r.log.scroll_by(e.delta_y().signum() as isize * 3);
+			}
+		}
+	}) as Box<dyn Fn(_)>);
+	canvas.set_onwheel(Some(onwheel.as_ref().unchecked_ref()));
+	onwheel.forget();
+	
+	let oncontextmenu = Closure::wrap(Box::new(move|e: MouseEvent| e.prevent_default()) as Box<dyn Fn(_)>); //long presses open the mouseover instead
+	canvas.set_oncontextmenu(Some(oncontextmenu.as_ref().unchecked_ref()));
+	oncontextmenu.forget();
 	
 	let repair_target2 = repair_target.clone();
 	let repair_button2 = repair_button.clone();
 	let juice_button2 = juice_button.clone();
 	let team2 = team.clone();
-	let canvas2 = canvas.clone();
 	let ws2 = ws.clone();
 	let state2 = state.clone();
 	let equipment2 = equipment.clone();
 	let selected2 = selected.clone();
-	let onmouseup = Closure::wrap(Box::new(move|e: MouseEvent| if e.button() == 0 {
-		if let Some(d) = b.replace(None) {
+	let press2 = press.clone();
+	let mouse2 = mouse.clone();
+	let viewport2 = viewport.clone();
+	let library2 = library.clone();
+	let presets2 = presets.clone();
+	let equipment_view2 = equipment_view.clone();
+	let unit_view2 = unit_view.clone();
+	let bulk2 = bulk.clone();
+	let box_select2 = box_select.clone();
+	let gold2 = gold.clone();
+	let messages2 = messages.clone();
+	let summary2 = summary.clone();
+	let pending2 = pending.clone();
+	let history2 = history.clone();
+	let stats2 = stats.clone();
+	let confirmations2 = confirmations.clone();
+	let settings2 = settings.clone();
+	let onpointerup = Closure::wrap(Box::new(move|e: PointerEvent| if e.is_primary() && e.button() == 0 {
+		let vp = viewport2.get();
+		let p = press2.replace(None);
+		let long_press = p.map(|p| p.long).unwrap_or(false);
+		if p.map(|p| p.touch && !p.long).unwrap_or(false) {
+			mouse2.set(NO_POINTER);
+		}
+		if long_press {
+			next_click.set(false); //the long press only opened a mouseover, so it shouldn't also count as a tap
+		}
+		let mut transfer = None;
+		if let Some(d) = b.replace(None).filter(|_| !library2.borrow().open && !presets2.borrow().open && !stats2.borrow().open && summary2.borrow().is_none() && !confirmations2.borrow().is_open() && !settings2.borrow().open) {
 			let t = team2.borrow();
 			let eq = equipment2.borrow();
 			let state = state2.borrow();
-			let m = screen_coords(e.client_x(), e.client_y(), &canvas2);
-			next_click.set((d - m).magnitude() < 2e-2);
+			let m = vp.screen_coords(e.client_x(), e.client_y());
+			next_click.set((d - m).magnitude() < TAP_DISTANCE);
+			if box_select2.get() && (d - m).magnitude() >= TAP_DISTANCE {
+				//a shift or ctrl drag picks everything it covers for the bulk actions rather than moving anything
+				let (min, max) = (vec2(d.x.min(m.x), d.y.min(m.y)), vec2(d.x.max(m.x), d.y.max(m.y)));
+				let mut bulk = bulk2.borrow_mut();
+				for (item, pos) in equipment_positions(&vp, &eq, state.storage().map(|(_us, es)| es), &equipment_view2.borrow()) {
+					let centre = pos + equip_box_thing_size() * 0.5;
+					if rect(centre, min, max - min) {
+						bulk.add(item);
+					}
+				}
+				return;
+			}
 			let mut from = None;
 			let mut to = None;
 			for i in 0..t.len() {
-				if let Some(0) = t[i].collides(d, team_unit_pos(t.len(), i, t[i].size(Vec2::one(), false)), Vec2::one(), false) {
+				if let Some(0) = t[i].collides(&vp, d, team_unit_pos(t.len(), i, t[i].size(Vec2::one(), false)), Vec2::one(), false) {
 					from = Some(InventoryType::Team(i));
 				}
-				if let Some(0) = t[i].collides(m, team_unit_pos(t.len(), i, t[i].size(Vec2::one(), false)), Vec2::one(), false) {
+				if let Some(0) = t[i].collides(&vp, m, team_unit_pos(t.len(), i, t[i].size(Vec2::one(), false)), Vec2::one(), false) {
 					to = Some(InventoryType::Team(i));
 				}
 			}
 			for i in 0..eq.len() {
-				if eq[i].collides(d, equip_pos(i) + equip_box_pos(), equip_scale(), false).is_some() {
+				if eq[i].collides(&vp, d, equip_pos(i) + equip_box_pos(&vp), equip_scale(), false).is_some() {
 					from = Some(InventoryType::EquipmentStorage(false, i));
 				}
-				if eq[i].collides(m, equip_pos(i) + equip_box_pos(), equip_scale(), false).is_some() {
+				if eq[i].collides(&vp, m, equip_pos(i) + equip_box_pos(&vp), equip_scale(), false).is_some() {
 					to = Some(InventoryType::EquipmentStorage(false, i));
 				}
 			}
 			state.storage().map(|(us, es)| {
-				for i in 0..us.len() {
-					if let Some(0) = us[i].collides(d, storage_unit_pos(i, us[0].size(storage_unit_scale(), false)) + unit_storage_box_pos(), storage_unit_scale(), false) {
+				for (slot, &i) in unit_view2.borrow().unit_order(us).iter().enumerate() {
+					if let Some(0) = us[i].collides(&vp, d, storage_unit_pos(slot, us[0].size(storage_unit_scale(), false)) + unit_storage_box_pos(&vp), storage_unit_scale(), false) {
 						from = Some(InventoryType::UnitStorage(i));
 					}
-					if let Some(0) = us[i].collides(m, storage_unit_pos(i, us[0].size(storage_unit_scale(), false)) + unit_storage_box_pos(), storage_unit_scale(), false) {
+					if let Some(0) = us[i].collides(&vp, m, storage_unit_pos(slot, us[0].size(storage_unit_scale(), false)) + unit_storage_box_pos(&vp), storage_unit_scale(), false) {
 						to = Some(InventoryType::UnitStorage(i));
 					}
 				}
-				for i in 0..es.len() {
-					if es[i].collides(d, equip_pos(i) + safe_equip_box_pos(), equip_scale(), false).is_some() {
+				for (slot, &i) in equipment_view2.borrow().equipment_order(es).iter().enumerate() {
+					if es[i].collides(&vp, d, equip_pos(slot) + safe_equip_box_pos(&vp), equip_scale(), false).is_some() {
 						from = Some(InventoryType::EquipmentStorage(true, i));
 					}
-					if es[i].collides(m, equip_pos(i) + safe_equip_box_pos(), equip_scale(), false).is_some() {
+					if es[i].collides(&vp, m, equip_pos(slot) + safe_equip_box_pos(&vp), equip_scale(), false).is_some() {
 						to = Some(InventoryType::EquipmentStorage(true, i));
 					}
 				}
@@ -426,29 +831,40 @@ pub fn start() -> Result<(), JsValue> {
 				use InventoryType::*;
 				match *i {
 					Team(uidx) => {
-						if let Some(i) = s.collides(d, vec2(left_edge(), bottom_edge()), Vec2::one(), false) {
+						if let Some(i) = s.collides(&vp, d, vec2(vp.left_edge(), vp.bottom_edge()), Vec2::one(), false) {
 							from = Some(InventoryType::Unit{ in_team: true, uidx, eidx: EquipType::from_idx(i) });
 						}
-						if let Some(i) = s.collides(m, vec2(left_edge(), bottom_edge()), Vec2::one(), true) {
+						if let Some(i) = s.collides(&vp, m, vec2(vp.left_edge(), vp.bottom_edge()), Vec2::one(), true) {
 							to = Some(InventoryType::Unit{ in_team: true, uidx, eidx: EquipType::from_idx(i) });
 						}
 					},
 					UnitStorage(uidx) => {
-						if let Some(i) = s.collides(d, vec2(left_edge(), bottom_edge()), Vec2::one(), false) {
+						if let Some(i) = s.collides(&vp, d, vec2(vp.left_edge(), vp.bottom_edge()), Vec2::one(), false) {
 							from = Some(InventoryType::Unit{ in_team: false, uidx, eidx: EquipType::from_idx(i) });
 						}
-						if let Some(i) = s.collides(m, vec2(left_edge(), bottom_edge()), Vec2::one(), true) {
+						if let Some(i) = s.collides(&vp, m, vec2(vp.left_edge(), vp.bottom_edge()), Vec2::one(), true) {
 							to = Some(InventoryType::Unit{ in_team: false, uidx, eidx: EquipType::from_idx(i) });
 						}
 					},
-					_ => unimplemented!(),
+					_ => {}, //only units get selected, anything else has no equipment slots to drag to or from
 				}
 			}
 			let r = repair_button2.borrow();
-			if r.collides(d, Vec2::zero(), Vec2::one(), false).is_some() {
-				to.map(|to| send(&ws2, ClientPacket::Repair(repair_target2.get(), to)).expect(l!()));
-			} else if juice_button2.collides(m, Vec2::zero(), Vec2::one(), false).is_some() {
-				from.map(|from| send(&ws2, ClientPacket::Juice(from)).expect(l!()));
+			if r.collides(&vp, d, Vec2::zero(), Vec2::one(), false).is_some() {
+				//only items sitting in a storage grid can be priced, the server still turns down anything else it can't afford
+				let price = to.as_ref().and_then(|to| storage_item(to, &eq, state.storage().map(|(_us, es)| es))).map(|e| cost::repair(e, repair_target2.get()));
+				if price.map(|p| can_afford(p, gold2.get(), &messages2)).unwrap_or(true) {
+					if let Some(to) = to {
+						send_answered(&ws2, &pending2, ClientPacket::Repair(repair_target2.get(), to));
+					}
+				}
+			} else if juice_button2.collides(&vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+				let name = from.as_ref().and_then(|from| storage_item(from, &eq, state.storage().map(|(_us, es)| es))).map(|e| e.stat_name1().to_lowercase()).unwrap_or_else(|| "this item".to_string());
+				let lines = vec!(format!("juice {}?",name), "it will be destroyed".to_string());
+				let packets = from.and_then(|from| confirmations2.borrow_mut().ask(ConfirmKind::Juice, lines, vec!(ClientPacket::Juice(from))));
+				for p in packets.into_iter().flatten() {
+					send(&ws2, p).expect(l!());
+				}
 			} else {
 				let u_size = if t.is_empty() {
 					state.storage().map(|(unit_storage, _equipment_storage)| unit_storage[0].size(Vec2::one(), false)).unwrap_or(Vec2::zero())
@@ -457,15 +873,15 @@ pub fn start() -> Result<(), JsValue> {
 				let s = vec2(0.4, 0.4);
 				let size = x.size(s, true) * 10.0;
 				if state.is_safe_zone() && to.is_none() {
-					if rect(m, vec2(left_edge() + 0.06, 0.84) - vec2(0.0, u_size.y * 6.0), u_size * vec2(14.0, 7.0)) {
+					if rect(m, vec2(vp.left_edge() + 0.06, 0.84) - vec2(0.0, u_size.y * 6.0), u_size * vec2(14.0, 7.0)) {
 						let l = state.storage().unwrap().0.len() - if let Some(InventoryType::UnitStorage(_)) = from { 1 } else { 0 };
 						to = Some(InventoryType::UnitStorage(l));
-					} else if rect(m, vec2(right_edge() - 0.06, top_edge() - 0.06) - size, size) {
+					} else if rect(m, vec2(vp.right_edge() - 0.06, vp.top_edge() - 0.06) - size, size) {
 						let l = state.storage().unwrap().1.len() - if let Some(InventoryType::EquipmentStorage(true, _)) = from { 1 } else { 0 };
 						to = Some(InventoryType::EquipmentStorage(true, l));
 					}
 				}
-				if to.is_none() && rect(m, vec2(right_edge() - 0.06, bottom_edge() + 0.06) + vec2(-size.x, 0.0), size) {
+				if to.is_none() && rect(m, vec2(vp.right_edge() - 0.06, vp.bottom_edge() + 0.06) + vec2(-size.x, 0.0), size) {
 					let l = eq.len() - if let Some(InventoryType::EquipmentStorage(false, _)) = from { 1 } else { 0 };
 					to = Some(InventoryType::EquipmentStorage(false, l));
 				}
@@ -483,33 +899,88 @@ pub fn start() -> Result<(), JsValue> {
 					}
 				}
 				log!("{:?},{:?}",from,to);
-				from.map(|from| to.map(|to|	send(&ws2, ClientPacket::Transfer(from, to)).expect(l!())));
+				transfer = from.and_then(|from| to.map(|to| (from, to)));
 			}
 		}
+		if let Some((from, to)) = transfer {
+			let mut state = state2.borrow_mut();
+			let mut team = team2.borrow_mut();
+			let mut equipment = equipment2.borrow_mut();
+			history2.borrow_mut().record(&from, &to, &team, state.storage(), &equipment);
+			send_transfer(&ws2, &pending2, &mut selected2.borrow_mut(), &mut team, &mut state, &mut equipment, from, to);
+		}
 	}) as Box<dyn Fn(_)>);
-	canvas.set_onmouseup(Some(onmouseup.as_ref().unchecked_ref()));
-	onmouseup.forget();
+	canvas.set_onpointerup(Some(onpointerup.as_ref().unchecked_ref()));
+	onpointerup.forget();
 	
-	let canvas2 = canvas.clone();
-	let mouse = Rc::new(Cell::new(Vec2::zero()));
 	let mouse2 = mouse.clone();
-	let onmove = Closure::wrap(Box::new(move|e: MouseEvent| {
-			let pos = screen_coords(e.client_x(), e.client_y(), &canvas2);
-			mouse2.set(pos);
-		if let Some(_initial_pos) = c.get() {
-			let _delta = vec2(e.movement_x(), -e.movement_y()).f32() * 2.0 / canvas2.client_height() as f32;
-			//something.borrow_mut().drag(initial_pos, pos, delta);
+	let press2 = press.clone();
+	let viewport2 = viewport.clone();
+	let onmove = Closure::wrap(Box::new(move|e: PointerEvent| if e.is_primary() {
+		let vp = viewport2.get();
+		let pos = vp.screen_coords(e.client_x(), e.client_y());
+		match press2.get() {
+			Some(mut p) if p.touch => if !p.long && (p.moved || !p.is_tap(pos)) {
+				p.moved = true;
+				press2.set(Some(p));
+				mouse2.set(pos);
+			},
+			Some(_) => mouse2.set(pos),
+			None => if e.pointer_type() == "mouse" { mouse2.set(pos) },
 		}
 	}) as Box<dyn Fn(_)>);
-	canvas.set_onmousemove(Some(onmove.as_ref().unchecked_ref()));
+	canvas.set_onpointermove(Some(onmove.as_ref().unchecked_ref()));
 	onmove.forget();
 	
 	let repair_button2 = repair_button.clone();
 	let repair_target2 = repair_target.clone();
 	let repair_button_selected2 = repair_button_selected.clone();
+	let equipment_view2 = equipment_view.clone();
+	let unit_view2 = unit_view.clone();
+	let ws2 = ws.clone();
+	let history2 = history.clone();
+	let pending2 = pending.clone();
+	let selected2 = selected.clone();
+	let team2 = team.clone();
+	let state2 = state.clone();
+	let equipment2 = equipment.clone();
+	let messages2 = messages.clone();
+	let confirmations2 = confirmations.clone();
+	let gold2 = gold.clone();
+	let depth2 = depth.clone();
+	let move_options2 = move_options.clone();
+	let settings2 = settings.clone();
+	let recording2 = recording.clone();
 	let onkeydown = Closure::wrap(Box::new(move|k: KeyboardEvent| {
-		if repair_button_selected2.get() {
-			repair_button2.borrow_mut().name.push_str(&k.key());
+		let key = k.key();
+		if confirmations2.borrow().is_open() {
+			k.prevent_default();
+			let result = confirmations2.borrow_mut().key(&key);
+			if let Some(DialogResult::Confirm(kind, packets)) = result {
+				send_confirmed(&ws2, &pending2, kind, packets, &mut state2.borrow_mut(), &gold2, &depth2, &move_options2);
+			}
+		} else if settings2.borrow_mut().key(&key) {
+			k.prevent_default();
+		} else if equipment_view2.borrow_mut().key(&key) || unit_view2.borrow_mut().key(&key) {
+			k.prevent_default();
+		} else if (k.ctrl_key() || k.meta_key()) && settings2.borrow().prefs.bound(Action::Undo, &key) {
+			k.prevent_default();
+			let mut state = state2.borrow_mut();
+			if state.is_safe_zone() || state.is_looting() {
+				undo_transfer(&ws2, &history2, &pending2, &mut selected2.borrow_mut(), &mut team2.borrow_mut(), &mut state, &mut equipment2.borrow_mut(), &messages2);
+			}
+		} else if let (Some(r), Some(a)) = (recording2.borrow_mut().as_mut(), Action::ALL.iter().find(|&&a| settings2.borrow().prefs.bound(a, &key))) {
+			k.prevent_default();
+			match a {
+				Action::Pause => r.paused = !r.paused,
+				Action::StepBack => r.step(false),
+				Action::StepForward => r.step(true),
+				Action::Slower => r.slower(),
+				Action::Faster => r.faster(),
+				Action::Undo => {},
+			}
+		} else if repair_button_selected2.get() {
+			repair_button2.borrow_mut().name.push_str(&key);
 			if let Ok(n) = repair_button2.borrow().name.parse() {
 				repair_target2.set(n);
 			}
@@ -535,7 +1006,18 @@ pub fn start() -> Result<(), JsValue> {
 	let selected2 = selected.clone();
 	let state2 = state.clone();
 	let depth2 = depth.clone();
+	let library2 = library.clone();
+	let summary2 = summary.clone();
+	let advice2 = advice.clone();
+	let bulk2 = bulk.clone();
+	let pending2 = pending.clone();
+	let history2 = history.clone();
+	let dive_map2 = dive_map.clone();
+	let stats2 = stats.clone();
+	let scouting2 = scouting.clone();
+	let settings2 = settings.clone();
 	let gold2 = gold.clone();
+	let presets2 = presets.clone();
 	let onmessage = Closure::wrap(Box::new(move|e: MessageEvent| {
 		use self::ServerPacket::*;
 		let juice = juice2.clone();
@@ -553,7 +1035,18 @@ pub fn start() -> Result<(), JsValue> {
 		let selected = selected2.clone();
 		let state = state2.clone();
 		let depth = depth2.clone();
+		let library = library2.clone();
+		let summary = summary2.clone();
+		let advice = advice2.clone();
+		let bulk = bulk2.clone();
+		let pending = pending2.clone();
+		let history = history2.clone();
+		let dive_map = dive_map2.clone();
+		let stats = stats2.clone();
+		let scouting = scouting2.clone();
+		let settings = settings2.clone();
 		let gold = gold2.clone();
+		let presets = presets2.clone();
 		recv(&e, move|p| match p {
 			Message(m) => {
 				document.get_element_by_id("login result").map(|r| r.set_inner_html(&m));
@@ -573,12 +1066,23 @@ pub fn start() -> Result<(), JsValue> {
 					let selected = selected.clone();
 					let state = state.clone();
 					let depth = depth.clone();
+					let library = library.clone();
+					let summary = summary.clone();
+					let advice = advice.clone();
+					let bulk = bulk.clone();
+					let pending = pending.clone();
+					let history = history.clone();
+					let dive_map = dive_map.clone();
+					let stats = stats.clone();
+					let scouting = scouting.clone();
+					let settings = settings.clone();
 					let gold = gold.clone();
+					let presets = presets.clone();
 					use self::ServerPacket::*;
 					recv(&e, move|p| {
 						match p {
-							Message(m) => messages.borrow_mut().push((m, MESSAGE_DURATION)),
-							SafeZoneInfo(mo, mut u, mut e, j) => { move_options.replace(mo); juice.set(j);
+							Message(m) => messages.borrow_mut().server(m),
+							SafeZoneInfo(mo, mut u, mut e, j) => { move_options.replace(mo); juice.set(j); bulk.borrow_mut().clear(); dive_map.borrow_mut().reset(); stats.borrow_mut().end_run(); pending.borrow_mut().reset();
 								for u in u.iter_mut() {
 									for p in u.perks.iter_mut().chain(u.perk_choice.iter_mut().flat_map(|p| p.iter_mut())) {
 										let mut s = p.desc.chars().collect();
@@ -624,9 +1128,26 @@ pub fn start() -> Result<(), JsValue> {
 									if s.last().map(|&s| s == '\n').unwrap_or(false) { s.pop(); }
 									e.desc = s.iter().cloned().collect();
 								}
-								team.replace(t); depth.set(d+1); gold.set(g); equipment.replace(e);
+								team.replace(t); depth.set(d+1); gold.set(g); equipment.replace(e); bulk.borrow_mut().clear();
+								if !state.borrow().is_safe_zone() {
+									dive_map.borrow_mut().visit(d+1, g);
+									stats.borrow_mut().team(d+1, g, &team.borrow());
+								}
+								//every transfer gets the team sent back, so this is where the oldest guess can be checked
+								let mut state = state.borrow_mut();
+								let storage = match &mut *state { SafeZone(us, es) => Some((us, es)), _ => None };
+								let answer = pending.borrow_mut().reconcile(Inventory{ team: &mut team.borrow_mut(), storage, equipment: &mut equipment.borrow_mut() });
+								if let Some(Answer::Refused(_, _)) = answer {
+									messages.borrow_mut().warning("transfer refused by the server, undone".to_string());
+									refresh_selected(&mut selected.borrow_mut(), &team.borrow(), &state);
+									if let Some(s) = presets.borrow_mut().fail("was refused") {
+										messages.borrow_mut().error(s);
+									}
+								}
 							},
 							Opponent(mut o, name) => {
+								history.borrow_mut().clear(); //fights move things around, so the old indices mean nothing
+								stats.borrow_mut().opponent(name.as_str());
 								for o in o.iter_mut() {
 									for p in o.perks.iter_mut() {
 										let mut s = p.desc.chars().collect();
@@ -642,14 +1163,17 @@ pub fn start() -> Result<(), JsValue> {
 										});
 									}
 								}
+								advice.replace(Some(Advice::new(&team.borrow(), &o)));
+								scouting.borrow_mut().meet(name.as_str(), &o);
 								opponent.replace(o); opponent_name.replace(name); state.replace(InFight(false));
 							},
 							FightResult(mut r, name) => {
 								opponent_name.replace(name);
+								scouting.borrow_mut().result(name.as_str(), r.won);
 								if r.won {
-									messages.borrow_mut().push(("won fight".into(), MESSAGE_DURATION));
+									messages.borrow_mut().success("won fight".into());
 								} else {
-									messages.borrow_mut().push(("lost fight".into(), MESSAGE_DURATION));
+									messages.borrow_mut().warning("lost fight".into());
 								}
 								for x in r.stuff.values_mut() {
 									for p in x.0.iter_mut() {
@@ -666,24 +1190,32 @@ pub fn start() -> Result<(), JsValue> {
 										});
 									}
 								}
+								let loot: usize = r.stuff.values().map(|x| x.0.len() + x.1.iter().filter(|e| e.is_some()).count()).sum();
+								dive_map.borrow_mut().fought(r.won, loot);
+								stats.borrow_mut().fight(depth.get(), r.won);
 								state.replace(Looting);
-								recording.replace(Some((r, 0, false)));
+								summary.replace(Some(FightSummary::new(&r, team.borrow().clone(), gold.get())));
+								match check(&r) {
+									Ok(()) => {
+										let saved = library.borrow_mut().add(SavedReplay {
+											recording: r.clone(),
+											opponent: *opponent_name.borrow(),
+											depth: depth.get(),
+											won: r.won,
+										});
+										report_saved(saved, &messages);
+										if settings.borrow().prefs.autoplay {
+											recording.replace(Some(settings.borrow().replay(r)));
+										}
+									},
+									Err(e) => messages.borrow_mut().warning(format!("fight not saved: {}",e)),
+								}
 							}
 							Loot => {
 								state.replace(Looting);
 							},
 						}
-						let mut s = selected.borrow_mut();
-						let t = team.borrow();
-						let state = state.borrow();
-						if let Some((ref mut s, Some(i))) = &mut*s {
-							use InventoryType::*;
-							match *i {
-								Team(i) => t.get(i),
-								UnitStorage(i) => state.storage().and_then(|(us, _es)| us.get(i)),
-								_ => unimplemented!(),
-							}.and_then(|u| u.select(false).map(|s2| *s = s2));
-						}
+						refresh_selected(&mut selected.borrow_mut(), &team.borrow(), &state.borrow());
 					});
 				};
 				onmessage(e2.clone());
@@ -727,110 +1259,225 @@ pub fn start() -> Result<(), JsValue> {
 	document.get_element_by_id("login button").expect(l!()).add_event_listener_with_callback("click", onclick.as_ref().unchecked_ref())?;
 	onclick.forget();
 	
-	let mut frame_num = 0;
+	let mut last_frame = js_sys::Date::now();
 	*g.borrow_mut() = Some(Closure::wrap(Box::new(move|| {
+		let now = js_sys::Date::now();
+		let dt = ((now - last_frame) / 1000.0).min(MAX_FRAME_TIME);
+		last_frame = now;
 		context.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 		context.clear_depth(-1.1);
 		let mut verts = Vec::new();
 		let mut verts2 = Vec::new(); //because of transparency shenanigans
+		let vp = viewport.get();
 		{
 			let v = &mut verts;
 			let v2 = &mut verts2;
+			if let Some(mut p) = press.get() {
+				if !p.long && p.is_long(js_sys::Date::now()) {
+					p.long = true;
+					press.set(Some(p));
+					drag_pos.set(None);
+					mouse.set(p.pos);
+				}
+			}
 			let m = mouse.get();
-			let d = drag_pos.get();
+			let d = drag_pos.get().filter(|_| press.get().map(|p| !p.touch || p.moved).unwrap_or(true)); //a finger resting on something hasn't started dragging it yet
+			{
+				let mut state = state.borrow_mut();
+				let storage = match &mut *state { SafeZone(us, es) => Some((us, es)), _ => None };
+				let mut pending = pending.borrow_mut();
+				let mut team = team.borrow_mut();
+				let mut equipment = equipment.borrow_mut();
+				let mut inventory = Inventory{ team: &mut team, storage, equipment: &mut equipment };
+				if let Some(s) = pending.expired(dt) {
+					inventory.restore(s);
+					messages.borrow_mut().warning("transfer not confirmed, undone".to_string());
+					refresh_selected(&mut selected.borrow_mut(), &team, &state);
+					if let Some(s) = presets.borrow_mut().fail("was not confirmed") {
+						messages.borrow_mut().error(s);
+					}
+				}
+			}
+			//preset steps are guessed at like any other transfer, so their answers line up with the pending ones
+			let waiting = !pending.borrow().is_empty();
+			let event = state.borrow().storage().and_then(|(us, es)| presets.borrow_mut().update(waiting, &team.borrow(), us, es));
+			match event {
+				Some(PresetEvent::Send(from, to)) => send_transfer(&ws, &pending, &mut selected.borrow_mut(), &mut team.borrow_mut(), &mut state.borrow_mut(), &mut equipment.borrow_mut(), from, to),
+				Some(PresetEvent::Done(s)) => messages.borrow_mut().success(s),
+				Some(PresetEvent::Failed(s)) => messages.borrow_mut().error(s),
+				None => {},
+			}
 			let state = state.borrow();
+			let library_open = state.is_safe_zone() && (library.borrow().open || presets.borrow().open || stats.borrow().open);
 			let mut recording = recording.borrow_mut();
 			if let Some(r) = recording.as_mut() {
-				pause_button.borrow().draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-				rewind_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-				skip_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-				let (t, o) = r.0.get(r.1);
-				draw_team(v, v2, m, d, &t, false);
-				draw_opponent(v, v2, m, d, &o);
+				pause_button.borrow().draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				rewind_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				skip_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				step_back_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				step_forward_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				slower_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				faster_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+				let scrubbing = d.map(|d| r.timeline_collides(d)).unwrap_or(false);
+				if scrubbing {
+					r.seek_to_timeline(m);
+				}
+				r.draw_timeline(v, v2, m);
+				let i = r.snapshot();
+				r.log.draw(v, v2, &vp, m, i);
+				let (t, o) = r.recording.get(i);
+				let (t1, o1) = r.recording.get((i + 1).min(r.len().saturating_sub(1)));
+				let f = smoothstep(r.progress());
+				draw_team(v, v2, &vp, m, d, &lerp_units(&t[..], &t1[..], f), false);
+				draw_opponent(v, v2, &vp, m, d, &lerp_units(&o[..], &o1[..], f));
+				let (start, end, f) = r.animation_window();
+				let (ts, os) = r.recording.get(start);
+				let (te, oe) = r.recording.get(end);
+				draw_transition(v2, &vp, (&ts[..], &te[..]), (&os[..], &oe[..]), f);
 				draw_opponent_name(v2, *opponent_name.borrow());
-				frame_num += 1;
-				if frame_num % FRAMES_PER_SNAPSHOT == 0 && !r.2 {
-					r.1 += 1;
-					if r.1 >= r.0.snapshots.len() {
-						*recording = None;
-					}
+				if !scrubbing && r.advance(dt) {
+					*recording = None;
 				}
+			} else if let Some(s) = summary.borrow().as_ref() {
+				s.draw(v, v2, &vp, m, d, &team.borrow(), gold.get());
 			} else {
 				match &*state {
-					SafeZone(unit_storage, equipment_storage) => {
-						draw_equipment(v, v2, m, d, safe_equip_box_pos(), equipment_storage);
+					SafeZone(unit_storage, equipment_storage) => if library_open {
+						if library.borrow().open {
+							library.borrow().draw(v, v2, &vp, m);
+						} else if stats.borrow().open {
+							stats.borrow().draw(v, v2, &vp, m);
+						} else {
+							presets.borrow().draw(v, v2, &vp, m);
+						}
+					} else {
+						draw_equipment(v, v2, &vp, m, d, safe_equip_box_pos(&vp), equipment_storage, Some(&equipment_view.borrow()));
+						equipment_view.borrow().draw(v, v2, &vp, m, equip_view_pos(&vp));
 						let t = team.borrow();
-						draw_team(v, v2, m, d, &t, true);
-						purchase_unit_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-						repair_button.borrow().draw(v, v2, Vec2::zero(), Vec2::one(), m, d, true);
-						juice_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-						if t.iter().any(|u| u.hp + 1e-8 < u.max_hp) {
-							heal_all_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-						}
-						let mo = move_options.borrow();
-						if !mo.is_empty() {
-							let button_start = vec2(-(4.5 * 0.1 + 5.0), (mo.len() / 10) as f32 * 0.55 + 0.5) * mo[0].size(Vec2::one(), false);
-							let mut p = button_start;
-							let step = mo[0].size(Vec2::one(), false) * 1.1;
-							for i in 0..mo.len() {
-								mo[i].draw(v, v2, p, Vec2::one(), m, d, false);
-								if (i+1) % 10 == 0 {
-									p.y -= step.y;
-									p.x = button_start.x;
-								} else {
-									p.x += step.x;
+						draw_team(v, v2, &vp, m, d, &t, true);
+						for i in 0..t.len() {
+							let b = unit_heal_button(&t[i], team_unit_pos(t.len(), i, t[i].size(Vec2::one(), true)), Vec2::one());
+							if cost::heal(&t[i]) > 1e-8 {
+								if let Some(reason) = cost::shortfall(cost::heal(&t[i]), gold.get()) {
+									cost::draw_disabled(v2, &vp, m, &b, &reason);
 								}
 							}
 						}
-						draw_unit_storage(v, v2, m, d, unit_storage);
+						purchase_unit_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						if let Some(reason) = cost::shortfall(cost::unit_purchase(), gold.get()) {
+							cost::draw_disabled(v2, &vp, m, &purchase_unit_button, &reason);
+						}
+						repair_button.borrow().draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, true);
+						juice_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						if d.map(|d| repair_button.borrow().collides(&vp, d, Vec2::zero(), Vec2::one(), false).is_some()).unwrap_or(false) {
+							//dragging the repair button over an item shows what fixing it up would cost
+							if let Some((s, i)) = equipment_at(&vp, m, &equipment.borrow(), Some(equipment_storage), &equipment_view.borrow()) {
+								let e = if s { equipment_storage[i].clone() } else { equipment.borrow()[i].clone() };
+								let price = cost::repair(&e, repair_target.get());
+								let s = cost::shortfall(price, gold.get()).unwrap_or(format!("repair: ~{}",cost::label(price)));
+								draw_string(v2, (m + vec2(0.0, TEXT_SIZE.y)).extend(30.0), TEXT_SIZE * 0.5, &s, Some((Color(VERY_DARK_GREY), s.len())));
+							}
+						}
+						if t.iter().any(|u| u.hp + 1e-8 < u.max_hp) {
+							let b = Button { name: format!("heal all: {:.2}",cost::heal_all(&t)), pos: heal_all_button.pos, size: heal_all_button.size, tex: Color(GREEN), edge: None };
+							b.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+							if let Some(reason) = cost::cheapest_heal(&t).and_then(|c| cost::shortfall(c, gold.get())) {
+								cost::draw_disabled(v2, &vp, m, &b, &reason);
+							}
+						}
+						move_picker.borrow().draw(v, v2, &vp, m, d, &move_options.borrow(), t.len());
+						draw_unit_storage(v, v2, &vp, m, d, unit_storage, &unit_view.borrow());
+						unit_view.borrow().draw(v, v2, &vp, m, unit_view_pos(&vp));
+						bulk.borrow().draw(v, v2, &vp, m, bulk_panel_pos(&vp), &equipment.borrow(), equipment_storage, repair_target.get(), gold.get());
+						library.borrow().button(&vp).draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						presets.borrow().button(&vp).draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						stats.borrow().button(&vp).draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						history.borrow().draw(v, v2, &vp, m);
 					},
 					Looting => {
 						draw_depth(v2, depth.get());
 						let t = team.borrow();
-						draw_team(v, v2, m, d, &t, false);
-						up_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-						stay_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-						down_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
+						draw_team(v, v2, &vp, m, d, &t, false);
+						up_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						stay_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						down_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+						history.borrow().draw(v, v2, &vp, m);
+						dive_map.borrow().draw(v2, &vp);
 					},
 					InQueue => {
 						draw_depth(v2, depth.get());
+						dive_map.borrow().draw(v2, &vp);
 						let t = team.borrow();
-						draw_team(v, v2, m, d, &t, false);
+						draw_team(v, v2, &vp, m, d, &t, false);
 					},
 					InFight(chosen) => {
 						draw_depth(v2, depth.get());
+						dive_map.borrow().draw(v2, &vp);
 						let t = team.borrow();
-						draw_team(v, v2, m, d, &t, false);
+						draw_team(v, v2, &vp, m, d, &t, false);
 						let o = opponent.borrow();
-						draw_opponent(v, v2, m, d, &o);
+						draw_opponent(v, v2, &vp, m, d, &o);
 						if !chosen {
-							fight_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
-							do_not_button.draw(v, v2, Vec2::zero(), Vec2::one(), m, d, false);
+							fight_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+							do_not_button.draw(v, v2, &vp, Vec2::zero(), Vec2::one(), m, d, false);
+							if let Some(a) = advice.borrow().as_ref() {
+								a.draw(v, v2, &vp);
+							}
 						}
+						scouting.borrow().draw(v, v2, &vp, m);
 						draw_opponent_name(v2, opponent_name.borrow().clone());
 					},
 				}
 			}
 			let mut messages = messages.borrow_mut();
-			if !messages.is_empty() {
-				if messages[0].1 <= 0.0 {
-					messages.remove(0);
-				}
-				let mut p = vec2(right_edge(), bottom_edge());
-				for m in messages.iter_mut() {
-					draw_string_blended(v2, (p - vec2(TEXT_SIZE.x, 0.0) * m.0.len() as f32).extend(30.0), TEXT_SIZE, m.0.clone(), (m.1 / 2.0).min(1.0), [0.0; 4]);
-					m.1 -= 1.0 / 60.0;
-					p.y += TEXT_SIZE.y * 1.1;
-				}
+			messages.update(dt);
+			messages.draw(v, v2, &vp, m);
+			settings.borrow().draw(v, v2, &vp, m, &confirmations.borrow().skip);
+			draw_string(v2, (vec2(vp.left_edge(), vp.top_edge()) + vec2(TEXT_SIZE.x, -TEXT_SIZE.y) * 1.1).extend(0.0), TEXT_SIZE, &format!("gold: {:.2}",gold.get()), None);
+			if state.is_safe_zone() {
+				presets.borrow().draw_progress(v2, &vp);
 			}
-			draw_string(v2, (vec2(left_edge(), top_edge()) + vec2(TEXT_SIZE.x, -TEXT_SIZE.y) * 1.1).extend(0.0), TEXT_SIZE, &format!("gold: {:.2}",gold.get()), None);
 			if state.is_safe_zone() {
 				let s = format!("knife juice: {:.2}",juice.get());
-				draw_string(v2, (vec2(right_edge() - (s.len() as f32 + 1.1) * TEXT_SIZE.x, safe_equip_box_pos().y - TEXT_SIZE.y)).extend(0.0), TEXT_SIZE, &s, None);
+				draw_string(v2, (vec2(vp.right_edge() - (s.len() as f32 + 1.1) * TEXT_SIZE.x, safe_equip_box_pos(&vp).y - TEXT_SIZE.y)).extend(0.0), TEXT_SIZE, &s, None);
+			}
+			if !library_open {
+				selected.borrow().as_ref().map(|(s, i)| s.draw(v, v2, &vp, vec2(vp.left_edge(), vp.bottom_edge()), Vec2::one(), m, d, i.is_some()));
+				let e = equipment.borrow();
+				draw_equipment(v, v2, &vp, m, d, equip_box_pos(&vp), &e, None);
+				let safe = state.storage().filter(|_| recording.is_none() && summary.borrow().is_none()).map(|(_us, es)| es);
+				let bulk = bulk.borrow();
+				for (item, pos) in equipment_positions(&vp, &e, safe, &equipment_view.borrow()) {
+					if bulk.contains(item) {
+						bulk.draw_highlight(v, pos, equip_box_thing_size());
+					}
+				}
+				if safe.is_some() {
+					let pending = pending.borrow();
+					for to in pending.destinations() {
+						if let Some((pos, size)) = pending_marker_pos(&vp, to, &team.borrow(), state.storage(), &e, &equipment_view.borrow(), &unit_view.borrow()) {
+							pending.draw_marker(v2, pos, size);
+						}
+					}
+				}
+				if let Some(d) = d.filter(|_| box_select.get()) {
+					quad(v2, vec2(d.x.min(m.x), d.y.min(m.y)).extend(20.0), vec2((d.x - m.x).abs(), (d.y - m.y).abs()), Color([1.0, 1.0, 0.0, 0.2]));
+				}
+				if let Some((_, Some(i))) = &*selected.borrow() {
+					let t = team.borrow();
+					let unit = match *i {
+						InventoryType::Team(i) => t.get(i),
+						InventoryType::UnitStorage(i) => state.storage().and_then(|(us, _es)| us.get(i)),
+						_ => None,
+					};
+					let hovered = hovered_equipment(&vp, m, &e, state.storage().map(|(_us, es)| es), &equipment_view.borrow());
+					if let (Some(u), Some(h)) = (unit, hovered) {
+						let current = u.equipment[equip_slot(h.equip_type)].as_ref();
+						draw_equip_comparison(h, current, v2, &vp, m);
+					}
+				}
 			}
-			selected.borrow().as_ref().map(|(s, i)| s.draw(v, v2, vec2(left_edge(), bottom_edge()), Vec2::one(), m, d, i.is_some()));
-			let e = equipment.borrow();
-			draw_equipment(v, v2, m, d, equip_box_pos(), &e);
+			confirmations.borrow().draw(v2, &vp, m);
 		}
 		verts.extend(verts2.drain(..));
 		render(verts, &context);
@@ -841,18 +1488,26 @@ pub fn start() -> Result<(), JsValue> {
 	Ok(())
 }
 
-fn draw_unit_storage(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, m: Vec2<f32>, d: Option<Vec2<f32>>, u: &Vec<Unit>) {
-	quad(v, unit_storage_box_pos().extend(0.0), unit_storage_box_size(), Color(VERY_DARK_GREY));
+fn draw_unit_storage(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, d: Option<Vec2<f32>>, u: &Vec<Unit>, view: &InventoryView) {
+	quad(v, unit_storage_box_pos(vp).extend(0.0), unit_storage_box_size(), Color(VERY_DARK_GREY));
 	if !u.is_empty() {
 		let size = u[0].size(storage_unit_scale(), false);
-		for i in 0..u.len() {
-			u[i].draw(v, v2, storage_unit_pos(i, size) + unit_storage_box_pos(), storage_unit_scale(), m, d, false);
+		for (slot, &i) in view.unit_order(u).iter().enumerate() {
+			let pos = storage_unit_pos(slot, size) + unit_storage_box_pos(vp);
+			u[i].draw(v, v2, vp, pos, storage_unit_scale(), m, d, false);
+			if !view.unit_matches(&u[i]) {
+				view.dim(v2, pos, size);
+			}
 		}
 	}
 }
 
-fn unit_storage_box_pos() -> Vec2<f32> {
-	vec2(left_edge() + TEXT_SIZE.x, top_edge() - TEXT_SIZE.y) - vec2(0.0, unit_storage_box_size().y)
+fn unit_view_pos(vp: &Viewport) -> Vec2<f32> {
+	unit_storage_box_pos(vp) - vec2(0.0, CONTROL_SIZE.y * 1.2)
+}
+
+fn unit_storage_box_pos(vp: &Viewport) -> Vec2<f32> {
+	vec2(vp.left_edge() + TEXT_SIZE.x, vp.top_edge() - TEXT_SIZE.y) - vec2(0.0, unit_storage_box_size().y)
 }
 
 fn unit_storage_box_size() -> Vec2<f32> {
@@ -867,11 +1522,11 @@ fn storage_unit_scale() -> Vec2<f32> {
 	vec2(0.2, 0.2)
 }
 
-fn draw_team(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, m: Vec2<f32>, d: Option<Vec2<f32>>, t: &Vec<Unit>, heal_buttons: bool) {
+fn draw_team(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, d: Option<Vec2<f32>>, t: &Vec<Unit>, heal_buttons: bool) {
 	if !t.is_empty() {
 		let size = t[0].size(Vec2::one(), heal_buttons);
 		for i in 0..t.len() {
-			t[i].draw(v, v2, team_unit_pos(t.len(), i, size), Vec2::one(), m, d, heal_buttons);
+			t[i].draw(v, v2, vp, team_unit_pos(t.len(), i, size), Vec2::one(), m, d, heal_buttons);
 		}
 	}
 }
@@ -880,11 +1535,11 @@ fn team_unit_pos(len: usize, i: usize, size: Vec2<f32>) -> Vec2<f32> {
 	vec2((-(len as f32) / 2.0 - 0.1 + i as f32 * 1.1) * size.x, - 0.3 - size.y)
 }
 
-fn draw_opponent<T: Thing<Args=bool>>(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, m: Vec2<f32>, d: Option<Vec2<f32>>, o: &Vec<T>) {
+fn draw_opponent<T: Thing<Args=bool>>(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, d: Option<Vec2<f32>>, o: &Vec<T>) {
 	if !o.is_empty() {
 		let size = o[0].size(Vec2::one(), false);
 		for i in 0..o.len() {
-			o[i].draw(v, v2, opponent_unit_pos(o.len(), i, size), Vec2::one(), m, d, false);
+			o[i].draw(v, v2, vp, opponent_unit_pos(o.len(), i, size), Vec2::one(), m, d, false);
 		}
 	}
 }
@@ -893,10 +1548,14 @@ fn opponent_unit_pos(len: usize, i: usize, size: Vec2<f32>) -> Vec2<f32> {
 	vec2((-(len as f32) / 2.0 - 0.1 + i as f32 * 1.1) * size.x, 0.3)
 }
 
-fn draw_equipment(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, m: Vec2<f32>, d: Option<Vec2<f32>>, pos: Vec2<f32>, e: &Vec<Equipment>) {
+fn draw_equipment(v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, d: Option<Vec2<f32>>, pos: Vec2<f32>, e: &Vec<Equipment>, view: Option<&InventoryView>) {
 	quad(v, pos.extend(0.0), equip_box_size(), Color(VERY_DARK_GREY));
-	for i in 0..e.len() {
-		e[i].draw(v, v2, pos + equip_pos(i), equip_scale(), m, d, true);
+	let order = view.map(|view| view.equipment_order(e)).unwrap_or((0..e.len()).collect());
+	for (slot, &i) in order.iter().enumerate() {
+		e[i].draw(v, v2, vp, pos + equip_pos(slot), equip_scale(), m, d, true);
+		if let Some(view) = view.filter(|view| !view.equipment_matches(&e[i])) {
+			view.dim(v2, pos + equip_pos(slot), e[i].size(equip_scale(), false));
+		}
 	}
 }
 
@@ -908,12 +1567,134 @@ fn equip_box_size() -> Vec2<f32> {
 	equip_box_thing_size() * 10.1
 }
 
-fn safe_equip_box_pos() -> Vec2<f32> {
-	vec2(right_edge() - 0.06, top_edge() - 0.06) - equip_box_size()
+fn safe_equip_box_pos(vp: &Viewport) -> Vec2<f32> {
+	vec2(vp.right_edge() - 0.06, vp.top_edge() - 0.06) - equip_box_size()
+}
+
+//where each piece of equipment is drawn, as ((in safe storage, index), pos)
+fn equipment_positions(vp: &Viewport, run: &[Equipment], safe: Option<&Vec<Equipment>>, view: &InventoryView) -> Vec<((bool, usize), Vec2<f32>)> {
+	let mut positions: Vec<_> = (0..run.len()).map(|i| ((false, i), equip_pos(i) + equip_box_pos(vp))).collect();
+	if let Some(es) = safe {
+		positions.extend(view.equipment_order(es).into_iter().enumerate().map(|(slot, i)| ((true, i), equip_pos(slot) + safe_equip_box_pos(vp))));
+	}
+	positions
+}
+
+fn equipment_at(vp: &Viewport, m: Vec2<f32>, run: &[Equipment], safe: Option<&Vec<Equipment>>, view: &InventoryView) -> Option<(bool, usize)> {
+	equipment_positions(vp, run, safe, view).into_iter().find(|&((s, i), pos)| {
+		let e = if s { &safe.expect(l!())[i] } else { &run[i] };
+		e.collides(vp, m, pos, equip_scale(), false).is_some()
+	}).map(|(item, _)| item)
+}
+
+fn hovered_equipment<'a>(vp: &Viewport, m: Vec2<f32>, run: &'a [Equipment], safe: Option<&'a Vec<Equipment>>, view: &InventoryView) -> Option<&'a Equipment> {
+	equipment_at(vp, m, run, safe, view).and_then(|(s, i)| if s { safe.and_then(|es| es.get(i)) } else { run.get(i) })
+}
+
+//whether the player can pay, and if not a message saying why
+fn can_afford(price: f64, gold: f64, messages: &RefCell<Toasts>) -> bool {
+	match cost::shortfall(price, gold) {
+		Some(reason) => {
+			messages.borrow_mut().warning(reason);
+			false
+		},
+		None => true,
+	}
+}
+
+fn storage_item<'a>(i: &InventoryType, run: &'a [Equipment], safe: Option<&'a Vec<Equipment>>) -> Option<&'a Equipment> {
+	match *i {
+		InventoryType::EquipmentStorage(false, i) => run.get(i),
+		InventoryType::EquipmentStorage(true, i) => safe.and_then(|es| es.get(i)),
+		_ => None,
+	}
+}
+
+//the library gives up its oldest replays when local storage is full, which shouldn't happen without the player hearing about it
+fn report_saved(saved: Result<usize, String>, messages: &RefCell<Toasts>) {
+	match saved {
+		Ok(0) => {},
+		Ok(n) => messages.borrow_mut().warning(format!("local storage is full, {} old replays deleted",n)),
+		Err(e) => messages.borrow_mut().error(format!("couldn't save replays: {}",e)),
+	}
+}
+
+fn refresh_selected(selected: &mut Selected, t: &[Unit], state: &State) {
+	if let Some((ref mut s, Some(i))) = selected {
+		use InventoryType::*;
+		match *i {
+			Team(i) => t.get(i),
+			UnitStorage(i) => state.storage().and_then(|(us, _es)| us.get(i)),
+			_ => None, //only units get selected, so there's nothing to redraw and the selection is kept as it is
+		}.and_then(|u| u.select(false).map(|s2| *s = s2));
+	}
+}
+
+//shown straight away, the server's answer replaces the guess when it arrives
+fn send_transfer(ws: &WebSocket, pending: &RefCell<PendingTransfers>, selected: &mut Selected, team: &mut Vec<Unit>, state: &mut State, equipment: &mut Vec<Equipment>, from: InventoryType, to: InventoryType) {
+	send(ws, ClientPacket::Transfer(from.clone(), to.clone())).expect(l!());
+	let storage = match &mut *state { SafeZone(us, es) => Some((us, es)), _ => None };
+	if pending.borrow_mut().apply(Inventory{ team: &mut *team, storage, equipment: &mut *equipment }, from, to) {
+		refresh_selected(selected, team, state);
+	}
+}
+
+//purchases, heals, perk choices and repairs get the team sent back like transfers do, so they take a place among the pending ones
+fn send_answered(ws: &WebSocket, pending: &RefCell<PendingTransfers>, p: ClientPacket) {
+	let answered = match p { ClientPacket::Purchase(_) | ClientPacket::PerkChoice(..) | ClientPacket::Repair(..) => true, _ => false };
+	send(ws, p).expect(l!());
+	if answered {
+		pending.borrow_mut().other();
+	}
+}
+
+//sends what the player agreed to, along with whatever the client assumes the server does about it
+fn send_confirmed(ws: &WebSocket, pending: &RefCell<PendingTransfers>, kind: ConfirmKind, packets: Vec<ClientPacket>, state: &mut State, gold: &Cell<f64>, depth: &Cell<usize>, move_options: &RefCell<Vec<MoveOption>>) {
+	for p in packets {
+		send_answered(ws, pending, p);
+	}
+	if kind == ConfirmKind::Move {
+		gold.set(0.0);
+		depth.set(1);
+		*move_options.borrow_mut() = Vec::new();
+		*state = InQueue;
+	}
+}
+
+fn undo_transfer(ws: &WebSocket, history: &RefCell<TransferHistory>, pending: &RefCell<PendingTransfers>, selected: &mut Selected, team: &mut Vec<Unit>, state: &mut State, equipment: &mut Vec<Equipment>, messages: &RefCell<Toasts>) {
+	match history.borrow_mut().undo() {
+		Ok((from, to)) => send_transfer(ws, pending, selected, team, state, equipment, from, to),
+		Err(e) => messages.borrow_mut().warning(e),
+	}
+}
+
+//where a transfer that's still waiting on the server has put its item, appended items are always the last one in their list
+fn pending_marker_pos(vp: &Viewport, to: &InventoryType, t: &[Unit], storage: Option<(&Vec<Unit>, &Vec<Equipment>)>, run: &[Equipment], equipment_view: &InventoryView, unit_view: &InventoryView) -> Option<(Vec2<f32>, Vec2<f32>)> {
+	match *to {
+		InventoryType::Team(i) => t.get(i).map(|u| (team_unit_pos(t.len(), i, u.size(Vec2::one(), false)), u.size(Vec2::one(), false))),
+		InventoryType::UnitStorage(_) => storage.filter(|(us, _es)| !us.is_empty()).and_then(|(us, _es)| {
+			let size = us[0].size(storage_unit_scale(), false);
+			unit_view.unit_order(us).iter().position(|&i| i == us.len() - 1).map(|slot| (storage_unit_pos(slot, size) + unit_storage_box_pos(vp), size))
+		}),
+		InventoryType::EquipmentStorage(safe, _) => {
+			let len = if safe { storage.map(|(_us, es)| es.len()).unwrap_or(0) } else { run.len() };
+			equipment_positions(vp, run, storage.map(|(_us, es)| es), equipment_view).into_iter()
+				.find(|&(item, _)| len > 0 && item == (safe, len - 1)).map(|(_, pos)| (pos, equip_box_thing_size()))
+		},
+		InventoryType::Unit{..} => None,
+	}
+}
+
+fn bulk_panel_pos(vp: &Viewport) -> Vec2<f32> {
+	safe_equip_box_pos(vp) + vec2(-PANEL_SIZE.x - 0.02, equip_box_size().y - PANEL_SIZE.y)
+}
+
+fn equip_view_pos(vp: &Viewport) -> Vec2<f32> {
+	safe_equip_box_pos(vp) - vec2(0.0, TEXT_SIZE.y * 2.2 + CONTROL_SIZE.y)
 }
 
-fn equip_box_pos() -> Vec2<f32> {
-	vec2(right_edge() - 0.06, bottom_edge() + 0.06) - vec2(equip_box_size().x, 0.0)
+fn equip_box_pos(vp: &Viewport) -> Vec2<f32> {
+	vec2(vp.right_edge() - 0.06, vp.bottom_edge() + 0.06) - vec2(equip_box_size().x, 0.0)
 }
 
 fn equip_box_thing_size() -> Vec2<f32> {
@@ -0,0 +1,143 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+
+const SORTS: [&str; 2] = ["server", "name"];
+const COLUMNS: usize = 8;
+const HEADER_HEIGHT: f32 = 0.06;
+const BOTTOM: f32 = 0.05;
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.025, y: 0.025 };
+const TOOLTIP: [f32; 4] = [0.1, 0.1, 0.1, 0.9];
+
+pub enum MoveAction {
+	Sort,
+	Pick(usize),
+}
+
+//why an option can't be taken with the team as it is
+pub fn move_blocked(o: &MoveOption, team: usize) -> Option<String> {
+	if team == 0 {
+		Some("your team is empty".to_string())
+	} else if team > o.max_group_size.unwrap_or(usize::max_value()) {
+		Some(format!("at most {} units, the team has {}",o.max_group_size.unwrap_or(0),team))
+	} else {
+		None
+	}
+}
+
+fn description(o: &MoveOption) -> Vec<String> {
+	vec!(
+		o.name.to_lowercase(),
+		match o.max_group_size { Some(n) => format!("teams of up to {} units",n), None => "any team size".to_string() },
+		"starts a new run from depth 1".to_string(),
+		"gold is reset to 0".to_string(),
+	)
+}
+
+fn group_name(limit: Option<usize>) -> String {
+	match limit { Some(1) => "solo".to_string(), Some(n) => format!("up to {} units",n), None => "any team size".to_string() }
+}
+
+//the destinations from the safe zone, grouped by how many units they allow
+pub struct MovePicker {
+	pub sort: usize,
+}
+
+impl MovePicker {
+	pub fn new() -> Self {
+		Self { sort: 0 }
+	}
+
+	pub fn next_sort(&mut self) {
+		self.sort = (self.sort + 1) % SORTS.len();
+	}
+
+	//the groups in order, smallest limit first with no limit last, each with the real indices of its options in display order
+	fn groups(&self, mo: &[MoveOption]) -> Vec<(Option<usize>, Vec<usize>)> {
+		let mut limits: Vec<Option<usize>> = mo.iter().map(|o| o.max_group_size).collect();
+		limits.sort_by_key(|l| l.unwrap_or(usize::max_value()));
+		limits.dedup();
+		limits.into_iter().map(|l| {
+			let mut order: Vec<usize> = (0..mo.len()).filter(|&i| mo[i].max_group_size == l).collect();
+			if SORTS[self.sort] == "name" {
+				order.sort_by(|&a, &b| mo[a].name.cmp(&mo[b].name));
+			}
+			(l, order)
+		}).collect()
+	}
+
+	fn step() -> Vec2<f32> {
+		BUTTON_SIZE * 1.1
+	}
+
+	fn left() -> f32 {
+		-Self::step().x * COLUMNS as f32 * 0.5
+	}
+
+	fn height(&self, mo: &[MoveOption]) -> f32 {
+		self.groups(mo).iter().map(|(_, o)| HEADER_HEIGHT + Self::step().y * ((o.len() + COLUMNS - 1) / COLUMNS) as f32).sum()
+	}
+
+	//headers and the position of every option, laid out downwards so the bottom edge stays put however many there are
+	fn layout(&self, mo: &[MoveOption]) -> (Vec<(String, Vec2<f32>)>, Vec<(usize, Vec2<f32>)>) {
+		let mut headers = Vec::new();
+		let mut options = Vec::new();
+		let mut y = BOTTOM + self.height(mo);
+		for (l, order) in self.groups(mo) {
+			y -= HEADER_HEIGHT;
+			headers.push((group_name(l), vec2(Self::left(), y + (HEADER_HEIGHT - TEXT_SIZE.y) * 0.5)));
+			for (slot, &i) in order.iter().enumerate() {
+				if slot % COLUMNS == 0 { y -= Self::step().y; }
+				options.push((i, vec2(Self::left() + Self::step().x * (slot % COLUMNS) as f32, y)));
+			}
+		}
+		(headers, options)
+	}
+
+	fn sort_button(&self, mo: &[MoveOption]) -> Button {
+		Button {
+			name: format!("sort: {}",SORTS[self.sort]),
+			pos: vec2(Self::left(), BOTTOM + self.height(mo) + 0.02),
+			size: vec2(0.4, 0.06),
+			tex: Color(DARK_GREY),
+			edge: None,
+		}
+	}
+
+	pub fn click(&self, vp: &Viewport, m: Vec2<f32>, mo: &[MoveOption]) -> Option<MoveAction> {
+		if mo.is_empty() { return None; }
+		if self.sort_button(mo).collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+			return Some(MoveAction::Sort);
+		}
+		self.layout(mo).1.into_iter().find(|&(i, p)| mo[i].collides(vp, m, p, Vec2::one(), false).is_some()).map(|(i, _)| MoveAction::Pick(i))
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, d: Option<Vec2<f32>>, mo: &[MoveOption], team: usize) {
+		if mo.is_empty() { return; }
+		self.sort_button(mo).draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, d, false);
+		let (headers, options) = self.layout(mo);
+		for (s, p) in headers {
+			draw_string(v2, p.extend(10.0), TEXT_SIZE, &s, None);
+		}
+		let mut hovered = None;
+		for (i, p) in options {
+			mo[i].draw(v, v2, vp, p, Vec2::one(), m, d, false);
+			let b = Button { name: String::new(), pos: p, size: mo[i].size(Vec2::one(), false), tex: Color(GREY), edge: None };
+			match move_blocked(&mo[i], team) {
+				Some(reason) => crate::cost::draw_disabled(v2, vp, m, &b, &reason),
+				None => if rect(m, p, b.size) { hovered = Some(i) },
+			}
+		}
+		if let Some(i) = hovered {
+			let lines = description(&mo[i]);
+			let width = lines.iter().map(|s| s.len()).max().unwrap_or(0) as f32 * TEXT_SIZE.x + 0.04;
+			let size = vec2(width, lines.len() as f32 * TEXT_SIZE.y * 1.3 + 0.02);
+			let mut p = m + vec2(0.02, 0.02);
+			p.x = p.x.min(vp.right_edge() - size.x);
+			quad(v2, p.extend(29.0), size, Color(TOOLTIP));
+			for (j, s) in lines.iter().enumerate() {
+				draw_string(v2, (p + vec2(0.02, size.y - TEXT_SIZE.y * 1.3 * (j + 1) as f32)).extend(30.0), TEXT_SIZE, s, None);
+			}
+		}
+	}
+}
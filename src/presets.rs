@@ -0,0 +1,401 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::storage;
+use serde::{Serialize,Deserialize};
+
+const STORAGE_KEY: &str = "presets";
+const MAX_PRESETS: usize = 16;
+const MAX_STEPS: usize = 200; //a plan that keeps changing under us shouldn't send transfers forever
+
+const ROW_HEIGHT: f32 = 0.07;
+const PANEL_SIZE: Vec2<f32> = Vec2{ x: 2.1, y: ROW_HEIGHT * (MAX_PRESETS + 2) as f32 };
+
+//units and equipment don't have ids, so they're recognised by what can't change in the safe zone
+fn unit_key(u: &Unit) -> String {
+	let class = match u.class { Class::Melee => 'm', Class::Ranged => 'r' };
+	let element = match u.element { Element::Red => 'r', Element::Green => 'g', Element::Blue => 'b' };
+	let perks: Vec<&str> = u.perks.iter().map(|p| p.desc.as_str()).collect();
+	format!("{}{}:{}",class,element,perks.join("|"))
+}
+
+fn equip_key(e: &Equipment) -> String {
+	e.desc.clone()
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct UnitLayout {
+	pub unit: String,
+	pub equipment: Vec<Option<String>>,
+}
+
+impl UnitLayout {
+	fn new(u: &Unit) -> Self {
+		Self {
+			unit: unit_key(u),
+			equipment: u.equipment.iter().map(|e| e.as_ref().map(equip_key)).collect(),
+		}
+	}
+}
+
+#[derive(Debug,Clone,PartialEq)]
+struct Layout {
+	team: Vec<UnitLayout>,
+	storage: Vec<UnitLayout>,
+	equipment: Vec<String>,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Place {
+	Team(usize),
+	Storage(usize),
+	Equipment(usize),
+	Slot(bool, usize, usize), //in team, unit, equipment slot
+}
+
+impl Place {
+	fn inventory_type(self) -> InventoryType {
+		match self {
+			Place::Team(i) => InventoryType::Team(i),
+			Place::Storage(i) => InventoryType::UnitStorage(i),
+			Place::Equipment(i) => InventoryType::EquipmentStorage(true, i),
+			Place::Slot(in_team, uidx, e) => InventoryType::Unit{ in_team, uidx, eidx: EquipType::from_idx(e) },
+		}
+	}
+}
+
+impl Layout {
+	fn new(team: &[Unit], unit_storage: &[Unit], equipment_storage: &[Equipment]) -> Self {
+		Self {
+			team: team.iter().map(UnitLayout::new).collect(),
+			storage: unit_storage.iter().map(UnitLayout::new).collect(),
+			equipment: equipment_storage.iter().map(equip_key).collect(),
+		}
+	}
+
+	fn slot(&mut self, in_team: bool, uidx: usize, e: usize) -> &mut Option<String> {
+		let units = if in_team { &mut self.team } else { &mut self.storage };
+		&mut units[uidx].equipment[e]
+	}
+
+	//mirrors what the server does with a transfer, so the rest of a plan can be worked out before it answers
+	fn apply(&mut self, from: Place, to: Place) {
+		match (from, to) {
+			(Place::Team(i), Place::Team(j)) => {
+				let u = self.team.remove(i);
+				self.team.insert(j.min(self.team.len()), u);
+			},
+			(Place::Storage(i), Place::Team(j)) => {
+				let u = self.storage.remove(i);
+				self.team.insert(j.min(self.team.len()), u);
+			},
+			(Place::Team(i), Place::Storage(_)) => {
+				let u = self.team.remove(i);
+				self.storage.push(u);
+			},
+			(Place::Equipment(i), Place::Slot(in_team, uidx, e)) => {
+				let new = self.equipment.remove(i);
+				let old = std::mem::replace(self.slot(in_team, uidx, e), Some(new));
+				self.equipment.extend(old);
+			},
+			(Place::Slot(in_team, uidx, e), Place::Equipment(_)) => {
+				let old = self.slot(in_team, uidx, e).take();
+				self.equipment.extend(old);
+			},
+			(Place::Slot(a, b, c), Place::Slot(x, y, z)) => {
+				let old = self.slot(x, y, z).take();
+				let new = std::mem::replace(self.slot(a, b, c), old);
+				*self.slot(x, y, z) = new;
+			},
+			_ => {},
+		}
+	}
+
+	//somewhere the item can be taken from without undoing slots that are already where they should be
+	fn find_equipment(&self, key: &str, done: (usize, usize)) -> Option<Place> {
+		if let Some(i) = self.equipment.iter().position(|e| e == key) {
+			return Some(Place::Equipment(i));
+		}
+		for (uidx, u) in self.team.iter().enumerate() {
+			for (e, x) in u.equipment.iter().enumerate() {
+				if x.as_ref().map(|x| x == key).unwrap_or(false) && (uidx, e) > done {
+					return Some(Place::Slot(true, uidx, e));
+				}
+			}
+		}
+		for (uidx, u) in self.storage.iter().enumerate() {
+			if let Some(e) = u.equipment.iter().position(|x| x.as_ref().map(|x| x == key).unwrap_or(false)) {
+				return Some(Place::Slot(false, uidx, e));
+			}
+		}
+		None
+	}
+}
+
+//the transfers that get from the current layout to the target one
+fn plan(mut layout: Layout, target: &[UnitLayout]) -> Result<Vec<(Place, Place)>, String> {
+	let mut steps = Vec::new();
+	let mut step = |layout: &mut Layout, from, to| {
+		layout.apply(from, to);
+		steps.push((from, to));
+	};
+	//units that aren't part of the preset go first, so the team never has to grow past its limit
+	while let Some(i) = layout.team.iter().rposition(|u| !target.iter().any(|t| t.unit == u.unit)) {
+		let l = layout.storage.len();
+		step(&mut layout, Place::Team(i), Place::Storage(l));
+	}
+	for (k, t) in target.iter().enumerate() {
+		if layout.team.get(k).map(|u| u.unit == t.unit).unwrap_or(false) { continue; }
+		if let Some(j) = layout.team.iter().skip(k).position(|u| u.unit == t.unit) {
+			step(&mut layout, Place::Team(j + k), Place::Team(k));
+		} else if let Some(s) = layout.storage.iter().position(|u| u.unit == t.unit) {
+			step(&mut layout, Place::Storage(s), Place::Team(k));
+		} else {
+			return Err(format!("unit {} of the preset is gone",k + 1));
+		}
+	}
+	while layout.team.len() > target.len() {
+		let (i, l) = (layout.team.len() - 1, layout.storage.len());
+		step(&mut layout, Place::Team(i), Place::Storage(l));
+	}
+	for (k, t) in target.iter().enumerate() {
+		for e in 0..t.equipment.len().min(layout.team[k].equipment.len()) {
+			if layout.team[k].equipment[e] == t.equipment[e] { continue; }
+			match &t.equipment[e] {
+				None => {
+					let l = layout.equipment.len();
+					step(&mut layout, Place::Slot(true, k, e), Place::Equipment(l));
+				},
+				Some(key) => match layout.find_equipment(key, (k, e)) {
+					Some(from) => step(&mut layout, from, Place::Slot(true, k, e)),
+					None => return Err(format!("an item for unit {} is gone",k + 1)),
+				},
+			}
+		}
+	}
+	Ok(steps)
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct Preset {
+	pub name: String,
+	pub team: Vec<UnitLayout>,
+}
+
+struct PresetRun {
+	name: String,
+	target: Vec<UnitLayout>,
+	sent: usize,
+	remaining: usize,
+	before: Option<Layout>, //what things looked like when the last transfer was sent
+}
+
+pub enum PresetEvent {
+	Send(InventoryType, InventoryType),
+	Done(String),
+	Failed(String),
+}
+
+pub enum PresetAction {
+	Apply(usize),
+	Delete(usize),
+	Save,
+	Close,
+}
+
+pub struct Presets {
+	pub presets: Vec<Preset>,
+	pub open: bool,
+	run: Option<PresetRun>,
+}
+
+impl Presets {
+	pub fn load() -> Self {
+		Self {
+			presets: storage::load(STORAGE_KEY).unwrap_or_default(),
+			open: false,
+			run: None,
+		}
+	}
+
+	fn save(&self) {
+		storage::save(STORAGE_KEY, &self.presets);
+	}
+
+	pub fn add(&mut self, name: String, team: &[Unit]) {
+		self.presets.retain(|p| p.name != name);
+		self.presets.insert(0, Preset { name, team: team.iter().map(UnitLayout::new).collect() });
+		self.presets.truncate(MAX_PRESETS);
+		self.save();
+	}
+
+	pub fn remove(&mut self, i: usize) {
+		if i < self.presets.len() {
+			self.presets.remove(i);
+			self.save();
+		}
+	}
+
+	pub fn apply(&mut self, i: usize) {
+		self.run = self.presets.get(i).map(|p| PresetRun {
+			name: p.name.clone(),
+			target: p.team.clone(),
+			sent: 0,
+			remaining: 0,
+			before: None,
+		});
+	}
+
+	//the server turned a step down or stopped answering, the message says which preset was stopped
+	pub fn fail(&mut self, reason: &str) -> Option<String> {
+		self.run.take().map(|run| format!("{}: step {} {}",run.name,run.sent,reason))
+	}
+
+	//nothing is planned while transfers are still waiting on the server, so every step and the final check are made on what it confirmed
+	pub fn update(&mut self, waiting: bool, team: &[Unit], unit_storage: &[Unit], equipment_storage: &[Equipment]) -> Option<PresetEvent> {
+		if waiting { return None; }
+		let mut run = self.run.take()?;
+		let current = Layout::new(team, unit_storage, equipment_storage);
+		if run.before.as_ref() == Some(&current) {
+			return Some(PresetEvent::Failed(format!("{}: step {} was not applied",run.name,run.sent)));
+		}
+		match plan(current.clone(), &run.target) {
+			Err(e) => Some(PresetEvent::Failed(format!("{}: {}",run.name,e))),
+			Ok(ref steps) if steps.is_empty() => Some(PresetEvent::Done(format!("applied {}",run.name))),
+			Ok(_) if run.sent >= MAX_STEPS => Some(PresetEvent::Failed(format!("{}: gave up after {} steps",run.name,run.sent))),
+			Ok(steps) => {
+				let (from, to) = steps[0];
+				run.sent += 1;
+				run.remaining = steps.len() - 1;
+				run.before = Some(current);
+				self.run = Some(run);
+				Some(PresetEvent::Send(from.inventory_type(), to.inventory_type()))
+			},
+		}
+	}
+
+	pub fn button(&self, vp: &Viewport) -> Button {
+		Button {
+			name: "presets".to_string(),
+			pos: vec2(vp.left_edge() + crate::TEXT_SIZE.x, -0.32),
+			size: BUTTON_SIZE,
+			tex: Color(BLUE),
+			edge: None,
+		}
+	}
+
+	fn panel_pos(&self) -> Vec2<f32> {
+		-PANEL_SIZE * 0.5
+	}
+
+	fn buttons(&self) -> Vec<(Button, PresetAction)> {
+		let size = vec2(0.25, ROW_HEIGHT * 0.8);
+		let right = self.panel_pos().x + PANEL_SIZE.x - 0.02;
+		let mut buttons = Vec::new();
+		for i in 0..self.presets.len() {
+			let y = self.panel_pos().y + PANEL_SIZE.y - ROW_HEIGHT * (i + 1) as f32;
+			buttons.push((Button { name: "apply".into(), pos: vec2(right - size.x * 2.1, y), size, tex: Color(GREEN), edge: None }, PresetAction::Apply(i)));
+			buttons.push((Button { name: "delete".into(), pos: vec2(right - size.x, y), size, tex: Color(DULL_RED), edge: None }, PresetAction::Delete(i)));
+		}
+		let y = self.panel_pos().y + 0.01;
+		buttons.push((Button { name: "save team".into(), pos: vec2(right - size.x * 2.1, y), size, tex: Color(PURPLE), edge: None }, PresetAction::Save));
+		buttons.push((Button { name: "close".into(), pos: vec2(right - size.x, y), size, tex: Color(GREY), edge: None }, PresetAction::Close));
+		buttons
+	}
+
+	pub fn click(&self, vp: &Viewport, m: Vec2<f32>) -> Option<PresetAction> {
+		self.buttons().into_iter().find(|(b, _)| b.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some()).map(|(_, a)| a)
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		quad(v, self.panel_pos().extend(0.0), PANEL_SIZE, Color(VERY_DARK_GREY));
+		let size = vec2(ROW_HEIGHT, ROW_HEIGHT) * 0.5;
+		if self.presets.is_empty() {
+			draw_string(v2, (self.panel_pos() + vec2(0.02, PANEL_SIZE.y - ROW_HEIGHT)).extend(10.0), size, "no saved presets", None);
+		}
+		for (i, p) in self.presets.iter().enumerate() {
+			let s = format!("{}  ({} units)",p.name.to_lowercase(),p.team.len());
+			let p = self.panel_pos() + vec2(0.02, PANEL_SIZE.y - ROW_HEIGHT * (i + 1) as f32 + (ROW_HEIGHT - size.y) * 0.5);
+			draw_string(v2, p.extend(10.0), size, &s, None);
+		}
+		for (b, _) in self.buttons() {
+			b.draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+		}
+	}
+
+	pub fn draw_progress(&self, v2: &mut Vec<Vertex>, vp: &Viewport) {
+		if let Some(run) = &self.run {
+			let s = format!("applying {}: step {}/{}",run.name.to_lowercase(),run.sent,run.sent + run.remaining);
+			draw_string(v2, vec3(-(s.len() as f32) * crate::TEXT_SIZE.x * 0.5, vp.top_edge() - crate::TEXT_SIZE.y * 1.5, 10.0), crate::TEXT_SIZE, &s, None);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn unit(name: &str, equipment: &[Option<&str>]) -> UnitLayout {
+		UnitLayout { unit: name.to_string(), equipment: equipment.iter().map(|e| e.map(|e| e.to_string())).collect() }
+	}
+
+	fn layout(team: Vec<UnitLayout>, storage: Vec<UnitLayout>, equipment: &[&str]) -> Layout {
+		Layout { team, storage, equipment: equipment.iter().map(|e| e.to_string()).collect() }
+	}
+
+	fn run(mut l: Layout, steps: &[(Place, Place)]) -> Layout {
+		for &(from, to) in steps {
+			l.apply(from, to);
+		}
+		l
+	}
+
+	#[test]
+	fn moving_a_unit_within_the_team_shifts_the_rest() {
+		let mut l = layout(vec!(unit("a", &[]), unit("b", &[]), unit("c", &[])), vec!(), &[]);
+		l.apply(Place::Team(0), Place::Team(2));
+		assert_eq!(l.team, vec!(unit("b", &[]), unit("c", &[]), unit("a", &[])));
+		l.apply(Place::Team(1), Place::Storage(0));
+		assert_eq!((l.team, l.storage), (vec!(unit("b", &[]), unit("a", &[])), vec!(unit("c", &[]))));
+	}
+
+	#[test]
+	fn equipping_over_an_item_sends_the_old_one_to_storage() {
+		let mut l = layout(vec!(unit("a", &[Some("old"), None])), vec!(), &["new"]);
+		l.apply(Place::Equipment(0), Place::Slot(true, 0, 0));
+		assert_eq!(l.team[0], unit("a", &[Some("new"), None]));
+		assert_eq!(l.equipment, vec!("old".to_string()));
+		l.apply(Place::Slot(true, 0, 0), Place::Slot(true, 0, 1));
+		assert_eq!(l.team[0], unit("a", &[None, Some("new")]));
+		l.apply(Place::Slot(true, 0, 1), Place::Equipment(1));
+		assert_eq!(l.team[0], unit("a", &[None, None]));
+		assert_eq!(l.equipment, vec!("old".to_string(), "new".to_string()));
+	}
+
+	#[test]
+	fn plan_reaches_the_target() {
+		let current = layout(
+			vec!(unit("a", &[Some("x"), None]), unit("b", &[None, None]), unit("c", &[None, None])),
+			vec!(unit("d", &[Some("y"), None])),
+			&["z"],
+		);
+		let target = vec!(unit("d", &[None, Some("x")]), unit("a", &[Some("z"), Some("y")]));
+		let steps = plan(current.clone(), &target).unwrap();
+		let l = run(current, &steps);
+		assert_eq!(l.team, target);
+		assert!(l.storage.iter().all(|u| u.unit == "b" || u.unit == "c"));
+	}
+
+	#[test]
+	fn plan_has_nothing_to_do_once_there() {
+		let current = layout(vec!(unit("a", &[Some("x")])), vec!(unit("b", &[None])), &[]);
+		assert_eq!(plan(current.clone(), &current.team.clone()).unwrap(), vec!());
+	}
+
+	#[test]
+	fn plan_fails_when_something_is_gone() {
+		let current = layout(vec!(unit("a", &[None])), vec!(), &[]);
+		assert!(plan(current.clone(), &[unit("b", &[None])]).is_err());
+		assert!(plan(current, &[unit("a", &[Some("x")])]).is_err());
+	}
+}
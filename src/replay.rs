@@ -0,0 +1,204 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::combat_log::CombatLog;
+
+pub const SNAPSHOTS_PER_SECOND: f64 = 60.0;
+const ANIMATION_TIME: f64 = 0.25; //at 1x, a single snapshot would go by too fast for the transitions to be followed
+pub const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+pub const NORMAL_SPEED: usize = 2;
+
+pub const TIMELINE_POS: Vec2<f32> = Vec2{ x: -0.8, y: -0.2 };
+pub const TIMELINE_SIZE: Vec2<f32> = Vec2{ x: 1.6, y: 0.04 };
+
+//recordings can come from files, so one has to have something to play and keep the same units all the way through before it's shown
+pub fn check(r: &FightRecording) -> Result<(), String> {
+	if r.snapshots.is_empty() { return Err("the replay has no fight in it".into()); }
+	let (t0, o0) = r.get(0);
+	if (1..r.snapshots.len()).map(|i| r.get(i)).all(|(t, o)| t.len() == t0.len() && o.len() == o0.len()) {
+		Ok(())
+	} else {
+		Err("the replay's units change partway through".into())
+	}
+}
+
+pub struct Replay {
+	pub recording: FightRecording,
+	pub cursor: f64, //in snapshots, fractional so playback speed isn't tied to the frame rate
+	pub paused: bool,
+	pub speed: usize,
+	pub log: CombatLog,
+}
+
+impl Replay {
+	pub fn new(recording: FightRecording) -> Self {
+		Self {
+			log: CombatLog::new(&recording),
+			recording,
+			cursor: 0.0,
+			paused: false,
+			speed: NORMAL_SPEED,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.recording.snapshots.len()
+	}
+
+	pub fn snapshot(&self) -> usize {
+		(self.cursor.max(0.0) as usize).min(self.len().saturating_sub(1))
+	}
+
+	//how far between the current snapshot and the next one playback is
+	pub fn progress(&self) -> f32 {
+		if self.snapshot() + 1 < self.len() {
+			(self.cursor - self.snapshot() as f64).max(0.0).min(1.0) as f32
+		} else {
+			0.0
+		}
+	}
+
+	//attacks, damage numbers and deaths are animated over groups of snapshots that take ANIMATION_TIME at 1x
+	//returns the first and last snapshot of the group playback is in and how far through it it is
+	pub fn animation_window(&self) -> (usize, usize, f32) {
+		let span = (ANIMATION_TIME * SNAPSHOTS_PER_SECOND).round().max(1.0);
+		let last = self.len().saturating_sub(1);
+		let start = ((self.cursor.max(0.0) / span).floor() * span) as usize;
+		let start = start.min(last);
+		let end = (start + span as usize).min(last);
+		if end > start {
+			(start, end, ((self.cursor - start as f64) / (end - start) as f64).max(0.0).min(1.0) as f32)
+		} else {
+			(start, end, 0.0)
+		}
+	}
+
+	//returns whether the replay has run off the end
+	pub fn advance(&mut self, dt: f64) -> bool {
+		if !self.paused {
+			self.cursor += dt * SNAPSHOTS_PER_SECOND * SPEEDS[self.speed];
+		}
+		self.cursor >= self.len() as f64
+	}
+
+	pub fn seek(&mut self, cursor: f64) {
+		self.cursor = cursor.max(0.0).min(self.len().saturating_sub(1) as f64);
+	}
+
+	//stepping pauses, otherwise the next frame would carry on from wherever it landed
+	pub fn step(&mut self, forward: bool) {
+		self.paused = true;
+		let i = self.snapshot() as f64;
+		self.seek(if forward { i + 1.0 } else { i - 1.0 });
+	}
+
+	pub fn faster(&mut self) {
+		self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+	}
+
+	pub fn slower(&mut self) {
+		self.speed = self.speed.saturating_sub(1);
+	}
+
+	pub fn timeline_collides(&self, m: Vec2<f32>) -> bool {
+		rect(m, TIMELINE_POS, TIMELINE_SIZE)
+	}
+
+	pub fn seek_to_timeline(&mut self, m: Vec2<f32>) {
+		let frac = ((m.x - TIMELINE_POS.x) / TIMELINE_SIZE.x).max(0.0).min(1.0);
+		self.seek((frac * self.len().saturating_sub(1) as f32).round() as f64);
+	}
+
+	pub fn draw_timeline(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, m: Vec2<f32>) {
+		let c = [DARK_GREY[0], DARK_GREY[1], DARK_GREY[2], DARK_GREY[3] * if self.timeline_collides(m) { 0.6 } else { 1.0 }];
+		quad(v, TIMELINE_POS.extend(1.0), TIMELINE_SIZE, Color(c));
+		let last = self.len().saturating_sub(1).max(1) as f32;
+		let tick = vec2((TIMELINE_SIZE.x / last * 0.25).min(0.005), TIMELINE_SIZE.y * 0.5);
+		for i in 0..self.len() {
+			let x = TIMELINE_POS.x + TIMELINE_SIZE.x * i as f32 / last;
+			quad(v, vec3(x - tick.x * 0.5, TIMELINE_POS.y + tick.y * 0.5, 2.0), tick, Color(GREY));
+		}
+		let progress = (self.cursor as f32 / last).max(0.0).min(1.0);
+		quad(v, TIMELINE_POS.extend(2.0), TIMELINE_SIZE * vec2(progress, 1.0), Color([GREY[0], GREY[1], GREY[2], 0.5]));
+		let marker = vec2(0.01, TIMELINE_SIZE.y * 1.5);
+		quad(v, vec3(TIMELINE_POS.x + TIMELINE_SIZE.x * progress - marker.x * 0.5, TIMELINE_POS.y - TIMELINE_SIZE.y * 0.25, 3.0), marker, Color(YELLOW));
+		let size = vec2(TIMELINE_SIZE.y, TIMELINE_SIZE.y);
+		let s = format!("{}/{}  {}x",self.snapshot() + 1,self.len(),SPEEDS[self.speed]);
+		draw_string(v2, (TIMELINE_POS + vec2(TIMELINE_SIZE.x - size.x * s.len() as f32, -size.y * 1.2)).extend(10.0), size, &s, None);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn replay(snapshots: usize) -> Replay {
+		Replay::new(FightRecording { won: false, snapshots: vec!((Vec::new(), Vec::new()); snapshots), stuff: Default::default() })
+	}
+
+	#[test]
+	fn cursor_splits_into_snapshot_and_progress() {
+		let mut r = replay(10);
+		r.cursor = 3.25;
+		assert_eq!((r.snapshot(), r.progress()), (3, 0.25));
+		r.cursor = 9.5;
+		assert_eq!((r.snapshot(), r.progress()), (9, 0.0));
+		r.cursor = 20.0;
+		assert_eq!(r.snapshot(), 9);
+	}
+
+	#[test]
+	fn advancing_follows_the_speed_and_stops_when_paused() {
+		let mut r = replay(100);
+		assert!(!r.advance(0.5));
+		assert_eq!(r.cursor, 0.5 * SNAPSHOTS_PER_SECOND);
+		r.faster();
+		r.advance(0.1);
+		assert_eq!(r.cursor, 0.5 * SNAPSHOTS_PER_SECOND + 0.1 * SNAPSHOTS_PER_SECOND * SPEEDS[NORMAL_SPEED + 1]);
+		r.paused = true;
+		let cursor = r.cursor;
+		r.advance(1.0);
+		assert_eq!(r.cursor, cursor);
+		r.paused = false;
+		assert!(r.advance(10.0));
+	}
+
+	#[test]
+	fn seeking_stays_inside_the_recording() {
+		let mut r = replay(10);
+		r.seek(4.0);
+		assert_eq!(r.cursor, 4.0);
+		r.seek(-3.0);
+		assert_eq!(r.cursor, 0.0);
+		r.seek(50.0);
+		assert_eq!(r.cursor, 9.0);
+		r.step(true);
+		assert_eq!(r.cursor, 9.0);
+		assert!(r.paused);
+		r.step(false);
+		assert_eq!(r.cursor, 8.0);
+	}
+
+	#[test]
+	fn animation_windows_cover_fixed_groups_of_snapshots() {
+		let span = (ANIMATION_TIME * SNAPSHOTS_PER_SECOND).round() as usize;
+		let mut r = replay(span * 2 + span / 2);
+		r.cursor = (span + span / 2) as f64;
+		let (start, end, f) = r.animation_window();
+		assert_eq!((start, end), (span, span * 2));
+		assert!((f - 0.5).abs() < 0.05);
+		//the last group is cut short by the end of the recording
+		r.cursor = (span * 2 + 1) as f64;
+		let (start, end, _) = r.animation_window();
+		assert_eq!((start, end), (span * 2, r.len() - 1));
+		r.cursor = 1e9;
+		let (start, end, f) = r.animation_window();
+		assert_eq!((start, end, f), (r.len() - 1, r.len() - 1, 0.0));
+	}
+
+	#[test]
+	fn empty_recordings_are_not_playable() {
+		assert!(check(&replay(0).recording).is_err());
+		assert!(check(&replay(3).recording).is_ok());
+	}
+}
@@ -0,0 +1,137 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::storage;
+use crate::replay::check;
+use serde::{Serialize,Deserialize};
+
+const STORAGE_KEY: &str = "replays";
+const MAX_SAVED: usize = 20; //local storage only has a few megabytes to go around, long fights can run out of it before this
+pub const FILE_EXTENSION: &str = ".rpsreplay";
+
+const ROW_HEIGHT: f32 = 0.07;
+const PANEL_SIZE: Vec2<f32> = Vec2{ x: 2.1, y: ROW_HEIGHT * (MAX_SAVED + 2) as f32 };
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct SavedReplay {
+	pub recording: FightRecording,
+	pub opponent: ArrayString<[u8; 32]>,
+	pub depth: usize,
+	pub won: bool,
+}
+
+impl SavedReplay {
+	pub fn file_name(&self) -> String {
+		format!("{}_depth{}{}",self.opponent,self.depth,FILE_EXTENSION)
+	}
+
+	pub fn import(data: &[u8]) -> Result<Self, String> {
+		let r: Self = deserialize(data).map_err(|_| "not a valid replay file".to_string())?;
+		check(&r.recording)?;
+		Ok(r)
+	}
+}
+
+pub enum LibraryAction {
+	Watch(usize),
+	Simulate(usize),
+	Export(usize),
+	Delete(usize),
+	Import,
+	Close,
+}
+
+pub struct ReplayLibrary {
+	pub replays: Vec<SavedReplay>,
+	pub open: bool,
+}
+
+impl ReplayLibrary {
+	pub fn load() -> Self {
+		Self {
+			replays: storage::load::<Vec<SavedReplay>>(STORAGE_KEY).unwrap_or_default().into_iter().filter(|r| check(&r.recording).is_ok()).collect(),
+			open: false,
+		}
+	}
+
+	pub fn add(&mut self, r: SavedReplay) -> Result<usize, String> {
+		self.replays.insert(0, r);
+		self.replays.truncate(MAX_SAVED);
+		self.save()
+	}
+
+	pub fn remove(&mut self, i: usize) -> Result<usize, String> {
+		if i < self.replays.len() {
+			self.replays.remove(i);
+		}
+		self.save()
+	}
+
+	//drops the oldest replays until the rest fit in local storage, returns how many had to go
+	fn save(&mut self) -> Result<usize, String> {
+		let mut dropped = 0;
+		loop {
+			match storage::try_save(STORAGE_KEY, &self.replays) {
+				Ok(()) => return Ok(dropped),
+				Err(e) if self.replays.is_empty() => return Err(e),
+				Err(_) => {
+					self.replays.pop();
+					dropped += 1;
+				},
+			}
+		}
+	}
+
+	pub fn button(&self, vp: &Viewport) -> Button {
+		Button {
+			name: "replays".to_string(),
+			pos: vec2(vp.left_edge() + crate::TEXT_SIZE.x, -0.2),
+			size: BUTTON_SIZE,
+			tex: Color(BLUE),
+			edge: None,
+		}
+	}
+
+	fn panel_pos(&self) -> Vec2<f32> {
+		-PANEL_SIZE * 0.5
+	}
+
+	//every row has its own simulate/watch/export/delete buttons, the last row holds import and close
+	fn buttons(&self) -> Vec<(Button, LibraryAction)> {
+		let size = vec2(0.25, ROW_HEIGHT * 0.8);
+		let right = self.panel_pos().x + PANEL_SIZE.x - 0.02;
+		let mut buttons = Vec::new();
+		for i in 0..self.replays.len() {
+			let y = self.panel_pos().y + PANEL_SIZE.y - ROW_HEIGHT * (i + 1) as f32;
+			buttons.push((Button { name: "simulate".into(), pos: vec2(right - size.x * 4.3, y), size, tex: Color(YELLOW), edge: None }, LibraryAction::Simulate(i)));
+			buttons.push((Button { name: "watch".into(), pos: vec2(right - size.x * 3.2, y), size, tex: Color(GREEN), edge: None }, LibraryAction::Watch(i)));
+			buttons.push((Button { name: "export".into(), pos: vec2(right - size.x * 2.1, y), size, tex: Color(CYAN), edge: None }, LibraryAction::Export(i)));
+			buttons.push((Button { name: "delete".into(), pos: vec2(right - size.x, y), size, tex: Color(DULL_RED), edge: None }, LibraryAction::Delete(i)));
+		}
+		let y = self.panel_pos().y + 0.01;
+		buttons.push((Button { name: "import".into(), pos: vec2(right - size.x * 2.1, y), size, tex: Color(PURPLE), edge: None }, LibraryAction::Import));
+		buttons.push((Button { name: "close".into(), pos: vec2(right - size.x, y), size, tex: Color(GREY), edge: None }, LibraryAction::Close));
+		buttons
+	}
+
+	pub fn click(&self, vp: &Viewport, m: Vec2<f32>) -> Option<LibraryAction> {
+		self.buttons().into_iter().find(|(b, _)| b.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some()).map(|(_, a)| a)
+	}
+
+	//drawn as its own screen rather than over the safe zone, since everything there would otherwise poke through
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		quad(v, self.panel_pos().extend(0.0), PANEL_SIZE, Color(VERY_DARK_GREY));
+		let size = vec2(ROW_HEIGHT, ROW_HEIGHT) * 0.5;
+		if self.replays.is_empty() {
+			draw_string(v2, (self.panel_pos() + vec2(0.02, PANEL_SIZE.y - ROW_HEIGHT)).extend(10.0), size, "no saved replays", None);
+		}
+		for (i, r) in self.replays.iter().enumerate() {
+			let s = format!("{} vs {}  depth {}",if r.won { "won" } else { "lost" },r.opponent.to_lowercase(),r.depth);
+			let p = self.panel_pos() + vec2(0.02, PANEL_SIZE.y - ROW_HEIGHT * (i + 1) as f32 + (ROW_HEIGHT - size.y) * 0.5);
+			draw_string(v2, p.extend(10.0), size, &s, None);
+		}
+		for (b, _) in self.buttons() {
+			b.draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+		}
+	}
+}
@@ -0,0 +1,143 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::storage;
+use serde::{Serialize,Deserialize};
+use std::collections::BTreeMap;
+
+const STORAGE_KEY: &str = "scouting";
+const MAX_NOTES: usize = 200;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.022, y: 0.022 };
+const PANEL_SIZE: Vec2<f32> = Vec2{ x: 0.8, y: 0.6 };
+const NOTES_WIDTH: usize = 24; //characters per line of notes in the sidebar
+
+//what could be seen of an opponent's team, anything unrevealed is counted separately
+#[derive(Debug,Clone,Default,Serialize,Deserialize)]
+pub struct Composition {
+	pub units: usize,
+	pub melee: usize,
+	pub ranged: usize,
+	pub red: usize,
+	pub green: usize,
+	pub blue: usize,
+	pub perks: usize,
+}
+
+impl Composition {
+	fn new(o: &[UnitView]) -> Self {
+		let mut c = Self { units: o.len(), ..Default::default() };
+		for u in o {
+			if u.class_revealed {
+				match u.unit.class { Class::Melee => c.melee += 1, Class::Ranged => c.ranged += 1 }
+			}
+			if u.element_revealed {
+				match u.unit.element { Element::Red => c.red += 1, Element::Green => c.green += 1, Element::Blue => c.blue += 1 }
+			}
+			c.perks += u.unit.perks.len();
+		}
+		c
+	}
+
+	fn lines(&self) -> Vec<String> {
+		vec!(
+			format!("{} units, {} perks",self.units,self.perks),
+			format!("{} melee, {} ranged, {} hidden",self.melee,self.ranged,self.units - self.melee - self.ranged),
+			format!("{} red, {} green, {} blue, {} hidden",self.red,self.green,self.blue,self.units - self.red - self.green - self.blue),
+		)
+	}
+}
+
+#[derive(Debug,Clone,Default,Serialize,Deserialize)]
+pub struct OpponentRecord {
+	pub met: usize,
+	pub won: usize,
+	pub lost: usize,
+	pub last_team: Composition,
+	pub notes: String,
+}
+
+//everything known about each opponent by name, kept between sessions
+pub struct Scouting {
+	records: BTreeMap<String, OpponentRecord>,
+	current: Option<(String, Option<OpponentRecord>)>, //the opponent being faced and what was known before this meeting
+}
+
+impl Scouting {
+	pub fn load() -> Self {
+		Self { records: storage::load(STORAGE_KEY).unwrap_or_default(), current: None }
+	}
+
+	fn save(&self) {
+		storage::save(STORAGE_KEY, &self.records);
+	}
+
+	pub fn meet(&mut self, name: &str, o: &[UnitView]) {
+		let before = self.records.get(name).cloned();
+		let r = self.records.entry(name.to_string()).or_default();
+		r.met += 1;
+		r.last_team = Composition::new(o);
+		self.current = Some((name.to_string(), before));
+		self.save();
+	}
+
+	pub fn result(&mut self, name: &str, won: bool) {
+		if let Some(r) = self.records.get_mut(name) {
+			if won { r.won += 1 } else { r.lost += 1 }
+			self.save();
+		}
+	}
+
+	pub fn current_name(&self) -> Option<&str> {
+		self.current.as_ref().map(|(n, _)| n.as_str())
+	}
+
+	pub fn notes(&self, name: &str) -> String {
+		self.records.get(name).map(|r| r.notes.clone()).unwrap_or_default()
+	}
+
+	pub fn set_notes(&mut self, name: &str, notes: String) {
+		self.records.entry(name.to_string()).or_default().notes = notes.chars().take(MAX_NOTES).collect();
+		self.save();
+	}
+
+	fn panel_pos(&self, vp: &Viewport) -> Vec2<f32> {
+		vec2(vp.right_edge() - 0.06 - PANEL_SIZE.x, -0.15 - PANEL_SIZE.y)
+	}
+
+	pub fn notes_button(&self, vp: &Viewport) -> Button {
+		let size = vec2(0.25, 0.056);
+		Button { name: "notes".into(), pos: self.panel_pos(vp) + vec2(PANEL_SIZE.x - size.x - 0.02, PANEL_SIZE.y - size.y - 0.02), size, tex: Color(PURPLE), edge: None }
+	}
+
+	//only says anything once a name comes round again, or if notes have already been written for it
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		let (name, before) = match &self.current {
+			Some(c) => c,
+			None => return,
+		};
+		let notes = self.notes(name);
+		let mut lines = Vec::new();
+		if let Some(r) = before {
+			lines.push(format!("met {} times before",r.met));
+			lines.push(format!("won {}, lost {}",r.won,r.lost));
+			lines.push("last time:".to_string());
+			lines.extend(r.last_team.lines().into_iter().map(|s| format!(" {}",s)));
+		}
+		if !notes.is_empty() {
+			lines.push("notes:".to_string());
+			let chars: Vec<char> = notes.chars().collect();
+			lines.extend(chars.chunks(NOTES_WIDTH).map(|c| format!(" {}",c.iter().collect::<String>())));
+		}
+		let pos = self.panel_pos(vp);
+		if !lines.is_empty() {
+			quad(v, pos.extend(0.0), PANEL_SIZE, Color(VERY_DARK_GREY));
+			let mut p = pos + vec2(0.02, PANEL_SIZE.y - TEXT_SIZE.y * 1.5);
+			for s in lines.iter().take(((PANEL_SIZE.y - 0.02) / (TEXT_SIZE.y * 1.3)) as usize) {
+				draw_string(v2, p.extend(10.0), TEXT_SIZE, s, None);
+				p.y -= TEXT_SIZE.y * 1.3;
+			}
+		}
+		self.notes_button(vp).draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+	}
+}
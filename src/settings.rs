@@ -0,0 +1,292 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::storage;
+use crate::dialog::ConfirmKind;
+use crate::replay::{Replay,SPEEDS,NORMAL_SPEED};
+use crate::toasts::DEFAULT_DURATION;
+use crate::viewport::MAX_UI_SCALE;
+use serde::{Serialize,Deserialize};
+use std::collections::BTreeMap;
+
+const STORAGE_KEY: &str = "settings";
+const TOAST_DURATIONS: [f64; 5] = [5.0, 10.0, DEFAULT_DURATION, 60.0, 120.0];
+const TOOLTIP_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+const UI_SCALES: [f32; 4] = [0.6, 0.75, 0.9, MAX_UI_SCALE]; //see MAX_UI_SCALE for why nothing goes above it
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.028, y: 0.028 };
+const PANEL_SIZE: Vec2<f32> = Vec2{ x: 2.0, y: 1.1 };
+const ROW_HEIGHT: f32 = 0.075;
+const VALUE_SIZE: Vec2<f32> = Vec2{ x: 0.34, y: 0.056 };
+const COLUMN_WIDTH: f32 = 0.96;
+const Z: f32 = 36.0;
+
+//reds and greens are swapped for an orange and a sky blue that stay apart with the common kinds of colour blindness
+pub fn element_color(vp: &Viewport, e: Element) -> [f32; 4] {
+	match (vp.display.colorblind, e) {
+		(false, Element::Red) => RED,
+		(false, Element::Green) => GREEN,
+		(false, Element::Blue) => BLUE,
+		(true, Element::Red) => [0.9, 0.6, 0.0, 1.0],
+		(true, Element::Green) => [0.35, 0.7, 0.9, 1.0],
+		(true, Element::Blue) => [0.0, 0.2, 0.8, 1.0],
+	}
+}
+
+pub fn class_color(vp: &Viewport, c: Class) -> [f32; 4] {
+	match (vp.display.colorblind, c) {
+		(false, Class::Melee) => DULL_RED,
+		(false, Class::Ranged) => DARK_GREEN,
+		(true, Class::Melee) => [0.6, 0.35, 0.0, 1.0],
+		(true, Class::Ranged) => [0.1, 0.3, 0.5, 1.0],
+	}
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
+pub enum Action {
+	Undo,
+	Pause,
+	StepBack,
+	StepForward,
+	Slower,
+	Faster,
+}
+
+impl Action {
+	pub const ALL: [Action; 6] = [Action::Undo, Action::Pause, Action::StepBack, Action::StepForward, Action::Slower, Action::Faster];
+
+	fn name(self) -> &'static str {
+		match self {
+			Action::Undo => "undo (ctrl +)",
+			Action::Pause => "pause replay",
+			Action::StepBack => "step back",
+			Action::StepForward => "step forward",
+			Action::Slower => "slower replay",
+			Action::Faster => "faster replay",
+		}
+	}
+
+	fn default_key(self) -> &'static str {
+		match self {
+			Action::Undo => "z",
+			Action::Pause => " ",
+			Action::StepBack => "ArrowLeft",
+			Action::StepForward => "ArrowRight",
+			Action::Slower => "[",
+			Action::Faster => "]",
+		}
+	}
+}
+
+fn key_name(key: &str) -> String {
+	match key {
+		" " => "space".to_string(),
+		k => k.to_lowercase(),
+	}
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct Prefs {
+	pub autoplay: bool,
+	pub replay_speed: usize,
+	pub toast_duration: f64,
+	pub tooltip_scale: f32,
+	pub ui_scale: f32,
+	pub colorblind: bool,
+	keys: BTreeMap<Action, String>, //only the ones moved off their default
+}
+
+impl Default for Prefs {
+	fn default() -> Self {
+		Self {
+			autoplay: true,
+			replay_speed: NORMAL_SPEED,
+			toast_duration: DEFAULT_DURATION,
+			tooltip_scale: 1.0,
+			ui_scale: 1.0,
+			colorblind: false,
+			keys: BTreeMap::new(),
+		}
+	}
+}
+
+impl Prefs {
+	pub fn key(&self, a: Action) -> &str {
+		self.keys.get(&a).map(|k| k.as_str()).unwrap_or(a.default_key())
+	}
+
+	pub fn bound(&self, a: Action, key: &str) -> bool {
+		self.key(a).eq_ignore_ascii_case(key)
+	}
+}
+
+pub enum SettingsAction {
+	Used,
+	Changed,
+	Confirm(ConfirmKind),
+}
+
+#[derive(Clone,Copy)]
+enum Row {
+	Autoplay,
+	Speed,
+	ToastDuration,
+	TooltipSize,
+	UiScale,
+	Colorblind,
+	Confirm(ConfirmKind),
+	Key(Action),
+	Reset,
+}
+
+fn next<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+	let i = options.iter().position(|&x| x == current).map(|i| i + 1).unwrap_or(0);
+	options[i % options.len()]
+}
+
+pub struct Settings {
+	pub prefs: Prefs,
+	pub open: bool,
+	rebinding: Option<Action>,
+}
+
+impl Settings {
+	pub fn load() -> Self {
+		let mut prefs: Prefs = storage::load(STORAGE_KEY).unwrap_or_default();
+		prefs.ui_scale = prefs.ui_scale.min(MAX_UI_SCALE); //larger scales used to be offered
+		Self { prefs, open: false, rebinding: None }
+	}
+
+	fn save(&self) {
+		storage::save(STORAGE_KEY, &self.prefs);
+	}
+
+	pub fn display(&self) -> DisplayPrefs {
+		DisplayPrefs { colorblind: self.prefs.colorblind, tooltip_scale: self.prefs.tooltip_scale }
+	}
+
+	pub fn replay(&self, recording: FightRecording) -> Replay {
+		let mut r = Replay::new(recording);
+		r.speed = self.prefs.replay_speed.min(SPEEDS.len() - 1);
+		r
+	}
+
+	pub fn button(&self, vp: &Viewport) -> Button {
+		Button {
+			name: "settings".to_string(),
+			pos: vec2(vp.right_edge() - 0.6, vp.top_edge() - 0.058),
+			size: vec2(0.22, 0.05),
+			tex: Color(if self.open { CYAN } else { DARK_GREY }),
+			edge: None,
+		}
+	}
+
+	fn panel_pos(&self) -> Vec2<f32> {
+		-PANEL_SIZE * 0.5
+	}
+
+	fn close_button(&self) -> Button {
+		let size = vec2(0.25, 0.056);
+		Button { name: "close".into(), pos: self.panel_pos() + vec2(PANEL_SIZE.x - 0.02 - size.x, 0.02), size, tex: Color(GREY), edge: None }
+	}
+
+	//general settings and confirmations down the left, keys down the right
+	fn rows(&self, skip: &[ConfirmKind]) -> Vec<(Row, String, Button)> {
+		let on_off = |b: bool| if b { "on" } else { "off" }.to_string();
+		let mut left = vec!(
+			(Row::Autoplay, "replay after fights".to_string(), on_off(self.prefs.autoplay)),
+			(Row::Speed, "replay speed".to_string(), format!("{}x",SPEEDS[self.prefs.replay_speed.min(SPEEDS.len() - 1)])),
+			(Row::ToastDuration, "message duration".to_string(), format!("{}s",self.prefs.toast_duration)),
+			(Row::TooltipSize, "tooltip size".to_string(), format!("{}%",(self.prefs.tooltip_scale * 100.0).round())),
+			(Row::UiScale, "ui scale".to_string(), format!("{}%",(self.prefs.ui_scale * 100.0).round())),
+			(Row::Colorblind, "colorblind palette".to_string(), on_off(self.prefs.colorblind)),
+		);
+		for &k in ConfirmKind::ALL.iter() {
+			left.push((Row::Confirm(k), format!("confirm {}",k.name()), if skip.contains(&k) { "don't ask" } else { "ask" }.to_string()));
+		}
+		left.push((Row::Reset, "all settings".to_string(), "reset".to_string()));
+		let right = Action::ALL.iter().map(|&a| {
+			let key = if self.rebinding == Some(a) { "press a key".to_string() } else { key_name(self.prefs.key(a)) };
+			(Row::Key(a), a.name().to_string(), key)
+		}).collect::<Vec<_>>();
+		let top = self.panel_pos() + vec2(0.04, PANEL_SIZE.y - 0.1);
+		let mut rows = Vec::new();
+		for (column, list) in vec!(left, right).into_iter().enumerate() {
+			for (i, (row, label, value)) in list.into_iter().enumerate() {
+				let pos = top + vec2(COLUMN_WIDTH * column as f32 + COLUMN_WIDTH - VALUE_SIZE.x - 0.06, -ROW_HEIGHT * i as f32);
+				let tex = match row { Row::Key(a) if self.rebinding == Some(a) => CYAN, Row::Reset => DULL_RED, _ => DARK_GREY };
+				rows.push((row, label, Button { name: value, pos, size: VALUE_SIZE, tex: Color(tex), edge: None }));
+			}
+		}
+		rows
+	}
+
+	//everything else waits while the panel is open, so any click on the screen is used up here
+	pub fn click(&mut self, vp: &Viewport, m: Vec2<f32>, skip: &[ConfirmKind]) -> Option<SettingsAction> {
+		if self.button(vp).collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+			self.open = !self.open;
+			self.rebinding = None;
+			return Some(SettingsAction::Used);
+		}
+		if !self.open { return None; }
+		self.rebinding = None;
+		if self.close_button().collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() || !rect(m, self.panel_pos(), PANEL_SIZE) {
+			self.open = false;
+			return Some(SettingsAction::Used);
+		}
+		let row = match self.rows(skip).into_iter().find(|(_, _, b)| b.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some()) {
+			Some((row, _, _)) => row,
+			None => return Some(SettingsAction::Used),
+		};
+		let p = &mut self.prefs;
+		match row {
+			Row::Autoplay => p.autoplay = !p.autoplay,
+			Row::Speed => p.replay_speed = (p.replay_speed + 1) % SPEEDS.len(),
+			Row::ToastDuration => p.toast_duration = next(&TOAST_DURATIONS, p.toast_duration),
+			Row::TooltipSize => p.tooltip_scale = next(&TOOLTIP_SCALES, p.tooltip_scale),
+			Row::UiScale => p.ui_scale = next(&UI_SCALES, p.ui_scale),
+			Row::Colorblind => p.colorblind = !p.colorblind,
+			Row::Confirm(k) => return Some(SettingsAction::Confirm(k)),
+			Row::Key(a) => {
+				self.rebinding = Some(a);
+				return Some(SettingsAction::Used);
+			},
+			Row::Reset => *p = Prefs::default(),
+		}
+		self.save();
+		Some(SettingsAction::Changed)
+	}
+
+	//returns whether the key was used, while the panel is open nothing behind it gets keys
+	pub fn key(&mut self, key: &str) -> bool {
+		if !self.open { return false; }
+		match self.rebinding.take() {
+			Some(_) if key == "Escape" => {},
+			Some(a) => {
+				if key == a.default_key() {
+					self.prefs.keys.remove(&a);
+				} else {
+					self.prefs.keys.insert(a, key.to_string());
+				}
+				self.save();
+			},
+			None => if key == "Escape" { self.open = false },
+		}
+		true
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, skip: &[ConfirmKind]) {
+		self.button(vp).draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+		if !self.open { return; }
+		quad(v2, self.panel_pos().extend(Z), PANEL_SIZE, Color([0.1, 0.1, 0.1, 0.97]));
+		let title = self.panel_pos() + vec2(0.04, PANEL_SIZE.y - 0.01 - TEXT_SIZE.y * 1.5);
+		draw_string(v2, title.extend(Z + 2.0), TEXT_SIZE, "settings", None);
+		draw_string(v2, (title + vec2(COLUMN_WIDTH, 0.0)).extend(Z + 2.0), TEXT_SIZE, "keys", None);
+		for (_, label, b) in self.rows(skip) {
+			let p = vec2(b.pos.x - COLUMN_WIDTH + VALUE_SIZE.x + 0.06, b.pos.y + (VALUE_SIZE.y - TEXT_SIZE.y) * 0.5);
+			draw_string(v2, p.extend(Z + 2.0), TEXT_SIZE, &label, None);
+			b.draw_over(v2, vp, m, Z + 1.0);
+		}
+		self.close_button().draw_over(v2, vp, m, Z + 1.0);
+	}
+}
@@ -0,0 +1,68 @@
+use crate::prelude::*;
+use crate::advisor::{Rng,sample};
+use crate::heuristics::{raw_damage,block_chance};
+
+const MAX_TICKS: usize = 300; //two sides that can't hurt each other would otherwise go forever
+const SEED: u64 = 0xf16;
+
+//a rough guess at the server's combat using the rules in heuristics, not a copy of it
+//perks aren't modelled since only their descriptions reach the client, the library warns about that whenever it runs this
+pub fn simulate(team: &[Unit], opponent: &[UnitView]) -> FightRecording {
+	let mut rng = Rng(SEED);
+	let mut t: Vec<Unit> = team.to_vec();
+	let mut o: Vec<UnitView> = opponent.iter().map(|o| {
+		let mut view = o.clone();
+		view.unit = sample(o, &mut rng);
+		view.unit.hp = view.unit.max_hp;
+		view.class_revealed = true;
+		view.element_revealed = true;
+		view.frac_hp_revealed = true;
+		view
+	}).collect();
+	let mut snapshots = vec!((t.clone(), o.clone()));
+	let alive = |u: &Unit| u.hp > 0.0;
+	for _ in 0..MAX_TICKS {
+		if !t.iter().any(alive) || !o.iter().any(|o| alive(&o.unit)) { break; }
+		//both sides hit at the same time, so work out the damage before applying any of it
+		let mut t_damage = vec!(0.0; t.len());
+		let mut o_damage = vec!(0.0; o.len());
+		for a in t.iter().filter(|u| alive(u)) {
+			if let Some(i) = target(a, o.iter().map(|o| &o.unit), &mut rng) {
+				if rng.next() >= block_chance(&o[i].unit) {
+					o_damage[i] += raw_damage(a, &o[i].unit);
+				}
+			}
+		}
+		for a in o.iter().map(|o| &o.unit).filter(|u| alive(u)) {
+			if let Some(i) = target(a, t.iter(), &mut rng) {
+				if rng.next() >= block_chance(&t[i]) {
+					t_damage[i] += raw_damage(a, &t[i]);
+				}
+			}
+		}
+		for (u, damage) in t.iter_mut().zip(t_damage).chain(o.iter_mut().map(|o| &mut o.unit).zip(o_damage)) {
+			if alive(u) {
+				u.hp = (u.hp - damage).min(u.max_hp);
+				if u.hp > 0.0 {
+					u.hp = (u.hp + u.regen).min(u.max_hp);
+				}
+			}
+		}
+		snapshots.push((t.clone(), o.clone()));
+	}
+	FightRecording {
+		won: t.iter().any(alive) && !o.iter().any(|o| alive(&o.unit)),
+		snapshots,
+		stuff: Default::default(),
+	}
+}
+
+//melee units go for the front of the line, ranged ones can pick anyone
+fn target<'a, I: Iterator<Item=&'a Unit>>(attacker: &Unit, defenders: I, rng: &mut Rng) -> Option<usize> {
+	let alive: Vec<usize> = defenders.enumerate().filter(|(_, u)| u.hp > 0.0).map(|(i, _)| i).collect();
+	if alive.is_empty() { return None; }
+	match attacker.class {
+		Class::Melee => Some(alive[0]),
+		Class::Ranged => Some(alive[rng.below(alive.len())]),
+	}
+}
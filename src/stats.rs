@@ -0,0 +1,184 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+use crate::storage;
+use serde::{Serialize,Deserialize};
+
+const STORAGE_KEY: &str = "stats";
+const MAX_RUNS: usize = 100;
+const GRAPHED_RUNS: usize = 20;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.035, y: 0.035 };
+const PANEL_SIZE: Vec2<f32> = Vec2{ x: 2.1, y: 1.6 };
+const GRAPH_SIZE: Vec2<f32> = Vec2{ x: 0.9, y: 0.45 };
+
+#[derive(Debug,Clone,Default,Serialize,Deserialize)]
+pub struct RunStats {
+	pub max_depth: usize,
+	pub gold: f64,
+	pub fights: Vec<(usize, bool)>, //depth and whether it was won
+	pub units_lost: usize,
+	pub opponents: Vec<String>,
+	team_size: usize,
+}
+
+impl RunStats {
+	fn won(&self) -> usize {
+		self.fights.iter().filter(|f| f.1).count()
+	}
+
+	fn lost(&self) -> usize {
+		self.fights.len() - self.won()
+	}
+}
+
+#[derive(Default,Serialize,Deserialize)]
+struct Saved {
+	runs: Vec<RunStats>,
+	current: Option<RunStats>,
+}
+
+//built up from the packets as they come in, the server doesn't keep any of this for us
+pub struct Stats {
+	runs: Vec<RunStats>,
+	current: Option<RunStats>,
+	pub open: bool,
+}
+
+impl Stats {
+	pub fn load() -> Self {
+		let saved: Saved = storage::load(STORAGE_KEY).unwrap_or_default();
+		Self { runs: saved.runs, current: saved.current, open: false }
+	}
+
+	//the run in progress is saved too, so closing the tab halfway down doesn't lose it
+	fn save(&self) {
+		storage::save(STORAGE_KEY, &Saved { runs: self.runs.clone(), current: self.current.clone() });
+	}
+
+	//a run starts with the first team sent outside the safe zone
+	pub fn team(&mut self, depth: usize, gold: f64, team: &[Unit]) {
+		let r = self.current.get_or_insert_with(|| RunStats { team_size: team.len(), ..Default::default() });
+		r.max_depth = r.max_depth.max(depth);
+		r.gold = gold;
+		r.units_lost += r.team_size.saturating_sub(team.len());
+		r.team_size = team.len();
+		self.save();
+	}
+
+	pub fn opponent(&mut self, name: &str) {
+		if let Some(r) = self.current.as_mut() {
+			r.opponents.push(name.to_string());
+			self.save();
+		}
+	}
+
+	pub fn fight(&mut self, depth: usize, won: bool) {
+		if let Some(r) = self.current.as_mut() {
+			r.fights.push((depth, won));
+			self.save();
+		}
+	}
+
+	//back in the safe zone, whatever happened down there is finished
+	pub fn end_run(&mut self) {
+		if let Some(r) = self.current.take() {
+			self.runs.insert(0, r);
+			self.runs.truncate(MAX_RUNS);
+			self.save();
+		}
+	}
+
+	fn all(&self) -> impl Iterator<Item=&RunStats> {
+		self.current.iter().chain(self.runs.iter())
+	}
+
+	//fights won and fought at each depth, starting from depth 1
+	fn by_depth(&self) -> Vec<(usize, usize)> {
+		let mut depths = Vec::new();
+		for &(d, won) in self.all().flat_map(|r| r.fights.iter()) {
+			let d = d.max(1);
+			if depths.len() < d { depths.resize(d, (0, 0)); }
+			let x: &mut (usize, usize) = &mut depths[d - 1];
+			x.0 += won as usize;
+			x.1 += 1;
+		}
+		depths
+	}
+
+	pub fn button(&self, vp: &Viewport) -> Button {
+		Button {
+			name: "stats".to_string(),
+			pos: vec2(vp.left_edge() + crate::TEXT_SIZE.x, -0.08),
+			size: BUTTON_SIZE,
+			tex: Color(BLUE),
+			edge: None,
+		}
+	}
+
+	fn panel_pos(&self) -> Vec2<f32> {
+		-PANEL_SIZE * 0.5
+	}
+
+	pub fn close_button(&self) -> Button {
+		let size = vec2(0.25, 0.056);
+		Button { name: "close".into(), pos: self.panel_pos() + vec2(PANEL_SIZE.x - 0.02 - size.x, 0.01), size, tex: Color(GREY), edge: None }
+	}
+
+	fn totals(&self) -> Vec<String> {
+		let runs = self.all().count();
+		let won: usize = self.all().map(|r| r.won()).sum();
+		let lost: usize = self.all().map(|r| r.lost()).sum();
+		let mut lines = vec!(
+			format!("runs: {}",runs),
+			format!("fights: {} won, {} lost",won,lost),
+			format!("win rate: {:.0}%",if won + lost > 0 { won as f64 / (won + lost) as f64 * 100.0 } else { 0.0 }),
+			format!("gold earned: {:.2}",self.all().map(|r| r.gold).sum::<f64>()),
+			format!("units lost: {}",self.all().map(|r| r.units_lost).sum::<usize>()),
+			format!("opponents met: {}",self.all().map(|r| r.opponents.len()).sum::<usize>()),
+		);
+		let best = self.all().max_by(|a, b| a.max_depth.cmp(&b.max_depth).then(a.gold.partial_cmp(&b.gold).unwrap_or(std::cmp::Ordering::Equal)));
+		if let Some(b) = best {
+			lines.push(String::new());
+			lines.push("best run:".to_string());
+			lines.push(format!("  depth {}, {:.2} gold",b.max_depth,b.gold));
+			lines.push(format!("  {} won, {} lost, {} units lost",b.won(),b.lost(),b.units_lost));
+		}
+		lines
+	}
+
+	//bars scaled so max reaches the top, with the title above and a label under each bar
+	fn draw_graph(&self, v2: &mut Vec<Vertex>, pos: Vec2<f32>, title: &str, bars: &[(f64, [f32; 4], String)], max: f64) {
+		draw_string(v2, (pos + vec2(0.0, GRAPH_SIZE.y + TEXT_SIZE.y * 0.5)).extend(10.0), TEXT_SIZE, title, None);
+		quad(v2, pos.extend(9.0), GRAPH_SIZE, Color([0.2, 0.2, 0.2, 1.0]));
+		if bars.is_empty() { return; }
+		let w = GRAPH_SIZE.x / bars.len() as f32;
+		let l = w.min(TEXT_SIZE.x * 0.7) * 0.5;
+		let label_size = vec2(l, l);
+		for (i, (x, c, label)) in bars.iter().enumerate() {
+			let h = (x / max.max(1e-8)).min(1.0) as f32 * GRAPH_SIZE.y;
+			quad(v2, (pos + vec2(w * (i as f32 + 0.1), 0.0)).extend(10.0), vec2(w * 0.8, h), Color(*c));
+			draw_string(v2, (pos + vec2(w * i as f32, -label_size.y * 1.5)).extend(10.0), label_size, label, None);
+		}
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		quad(v, self.panel_pos().extend(0.0), PANEL_SIZE, Color(VERY_DARK_GREY));
+		let mut p = self.panel_pos() + vec2(0.04, PANEL_SIZE.y - TEXT_SIZE.y * 2.0);
+		for s in self.totals() {
+			draw_string(v2, p.extend(10.0), TEXT_SIZE, &s, None);
+			p.y -= TEXT_SIZE.y * 1.3;
+		}
+		let right = self.panel_pos() + vec2(PANEL_SIZE.x - GRAPH_SIZE.x - 0.06, 0.0);
+		let depths: Vec<_> = self.by_depth().into_iter().enumerate()
+			.map(|(i, (won, fought))| (if fought > 0 { won as f64 / fought as f64 } else { 0.0 }, GREEN, format!("{}",i + 1)))
+			.collect();
+		self.draw_graph(v2, right + vec2(0.0, PANEL_SIZE.y - GRAPH_SIZE.y - TEXT_SIZE.y * 3.0), "win rate by depth", &depths, 1.0);
+		let runs: Vec<_> = self.all().take(GRAPHED_RUNS).collect::<Vec<_>>().into_iter().rev()
+			.map(|r| (r.max_depth as f64, if r.units_lost > 0 { DULL_RED } else { CYAN }, String::new()))
+			.collect();
+		let deepest = runs.iter().map(|r| r.0).fold(0.0, f64::max);
+		self.draw_graph(v2, right + vec2(0.0, 0.2), "max depth of recent runs", &runs, deepest);
+		self.close_button().draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+	}
+}
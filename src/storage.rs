@@ -0,0 +1,129 @@
+use crate::prelude::*;
+use crate::boiler_plate::window;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Storage,Blob,Url,FileReader,HtmlAnchorElement,HtmlInputElement};
+use js_sys::Uint8Array;
+use serde::de::DeserializeOwned;
+
+const REVOKE_DELAY: i32 = 10_000; //ms, revoking straight away can cancel the download before the browser has started it
+
+//local storage only holds strings, so everything is stored as hex encoded bincode
+pub fn local_storage() -> Option<Storage> {
+	window().local_storage().ok().and_then(|s| s)
+}
+
+fn encode(data: &[u8]) -> String {
+	data.iter().map(|b| format!("{:02x}",b)).collect()
+}
+
+fn decode(s: &str) -> Option<Vec<u8>> {
+	(0..s.len() / 2).map(|i| u8::from_str_radix(s.get(i*2..i*2+2)?, 16).ok()).collect()
+}
+
+//fails when local storage is missing or full, it's up to the caller what gets given up to make room
+pub fn try_save<T: Serialize>(key: &str, t: &T) -> Result<(), String> {
+	let data = serialize(t).map_err(|_| format!("couldn't encode {}",key))?;
+	let l = local_storage().ok_or("local storage isn't available")?;
+	l.set_item(key, &encode(&data)).map_err(|_| "local storage is full".to_string())
+}
+
+//for the small things, they only run out of room when the replays have taken it and the library makes room whenever it saves
+pub fn save<T: Serialize>(key: &str, t: &T) {
+	if let Err(e) = try_save(key, t) {
+		log!("failed to save {}: {}",key,e);
+	}
+}
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+	let s = local_storage()?.get_item(key).ok()??;
+	deserialize(&decode(&s)?).ok()
+}
+
+pub fn download(name: &str, data: &[u8]) -> Result<(), JsValue> {
+	let parts = js_sys::Array::new();
+	parts.push(&Uint8Array::from(data));
+	let blob = Blob::new_with_u8_array_sequence(&parts)?;
+	let url = Url::create_object_url_with_blob(&blob)?;
+	let a = window().document().ok_or("no document")?.create_element("a")?.dyn_into::<HtmlAnchorElement>()?;
+	a.set_href(&url);
+	a.set_download(name);
+	a.click();
+	let revoke = Closure::once_into_js(Box::new(move|| {
+		let _ = Url::revoke_object_url(&url);
+	}) as Box<dyn FnOnce()>);
+	window().set_timeout_with_callback_and_timeout_and_arguments_0(revoke.unchecked_ref(), REVOKE_DELAY)?;
+	Ok(())
+}
+
+//the callback is shared between the reader's handler and the error paths, whichever gets there first uses it
+fn finish<F: FnOnce(Result<Vec<u8>, String>)>(f: &RefCell<Option<F>>, data: Result<Vec<u8>, String>) {
+	if let Some(f) = f.borrow_mut().take() {
+		f(data);
+	}
+}
+
+//opens the browser's file picker and hands over the contents of whatever file gets chosen, or why it couldn't be read
+pub fn upload<F: 'static + FnOnce(Result<Vec<u8>, String>)>(accept: &str, f: F) -> Result<(), JsValue> {
+	let input = window().document().ok_or("no document")?.create_element("input")?.dyn_into::<HtmlInputElement>()?;
+	input.set_type("file");
+	input.set_accept(accept);
+	let input2 = input.clone();
+	let onchange = Closure::once_into_js(Box::new(move|| {
+		let file = match input2.files().and_then(|f| f.get(0)) {
+			Some(file) => file,
+			None => return,
+		};
+		let f = Rc::new(RefCell::new(Some(f)));
+		let r = match FileReader::new() {
+			Ok(r) => r,
+			Err(_) => return finish(&f, Err("couldn't open a file reader".into())),
+		};
+		let r2 = r.clone();
+		let f2 = f.clone();
+		//loadend comes after failed reads too, they just leave the result null
+		let onload = Closure::once_into_js(Box::new(move|| {
+			let data = r2.result().ok().filter(|r| !r.is_null()).map(|r| {
+				let data = Uint8Array::new(&r);
+				let mut readable = vec!(0; data.length() as usize);
+				data.copy_to(&mut readable);
+				readable
+			});
+			finish(&f2, data.ok_or_else(|| "couldn't read the file".to_string()));
+		}) as Box<dyn FnOnce()>);
+		if r.add_event_listener_with_callback("loadend", onload.unchecked_ref()).and_then(|_| r.read_as_array_buffer(&file)).is_err() {
+			finish(&f, Err("couldn't read the file".into()));
+		}
+	}) as Box<dyn FnOnce()>);
+	input.add_event_listener_with_callback("change", onchange.unchecked_ref())?;
+	input.click();
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hex_round_trips() {
+		let data: Vec<u8> = (0..=255).collect();
+		let s = encode(&data);
+		assert_eq!(s.len(), data.len() * 2);
+		assert_eq!(&s[..8], "00010203");
+		assert_eq!(decode(&s), Some(data));
+		assert_eq!(decode(""), Some(Vec::new()));
+	}
+
+	#[test]
+	fn bad_hex_is_rejected() {
+		assert_eq!(decode("0g"), None);
+		assert_eq!(decode("é0"), None);
+	}
+
+	#[test]
+	fn stored_values_decode_to_what_was_saved() {
+		let saved = (3u32, "abc".to_string(), vec!(1.5f64, -2.0));
+		let loaded: (u32, String, Vec<f64>) = deserialize(&decode(&encode(&serialize(&saved).unwrap())).unwrap()).unwrap();
+		assert_eq!(loaded, saved);
+	}
+}
@@ -0,0 +1,100 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.04, y: 0.04 };
+const TOP: f32 = 0.85;
+
+pub struct FightSummary {
+	pub won: bool,
+	pub gold_before: f64,
+	pub hp_lost: Vec<f64>,
+	pub perks: Vec<Perk>,
+	pub equipment: Vec<Equipment>,
+	pub team_before: Vec<Unit>, //durability is compared against whatever team the server sends after the fight
+}
+
+impl FightSummary {
+	pub fn new(r: &FightRecording, team_before: Vec<Unit>, gold_before: f64) -> Self {
+		let hp_lost = if r.snapshots.is_empty() { Vec::new() } else {
+			let (first, _) = r.get(0);
+			let (last, _) = r.get(r.snapshots.len() - 1);
+			first.iter().enumerate().map(|(i, u)| u.hp - last.get(i).map(|u| u.hp.max(0.0)).unwrap_or(0.0)).collect()
+		};
+		let perks = r.stuff.values().flat_map(|x| x.0.iter().cloned()).collect();
+		let equipment = r.stuff.values().flat_map(|x| x.1.iter().filter_map(|e| e.clone())).collect();
+		Self {
+			won: r.won,
+			gold_before,
+			hp_lost,
+			perks,
+			equipment,
+			team_before,
+		}
+	}
+
+	pub fn continue_button(&self) -> Button {
+		Button {
+			name: "continue".to_string(),
+			pos: vec2(-BUTTON_SIZE.x * 1.25, -0.9),
+			size: BUTTON_SIZE * vec2(2.5, 1.5),
+			tex: Color(GREEN),
+			edge: None,
+		}
+	}
+
+	fn durability_lost(&self, team: &[Unit]) -> Vec<String> {
+		let mut lines = Vec::new();
+		for (i, (before, after)) in self.team_before.iter().zip(team.iter()).enumerate() {
+			for (b, a) in before.equipment.iter().zip(after.equipment.iter()) {
+				if let (Some(b), Some(a)) = (b, a) {
+					if b.stat_name1() == a.stat_name1() && b.durability - a.durability > 1e-8 {
+						lines.push(format!("unit {} {} item: -{:.3}",i + 1,b.stat_name1(),b.durability - a.durability));
+					}
+				}
+			}
+		}
+		lines
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, d: Option<Vec2<f32>>, team: &[Unit], gold: f64) {
+		let x = -0.8;
+		let mut y = TOP;
+		let mut line = |v2: &mut Vec<Vertex>, s: &str| {
+			draw_string(v2, vec3(x, y, 10.0), TEXT_SIZE, s, None);
+			y -= TEXT_SIZE.y * 1.3;
+		};
+		line(v2, if self.won { "won fight" } else { "lost fight" });
+		line(v2, &format!("gold: {:+.2}",gold - self.gold_before));
+		line(v2, "hp lost:");
+		for (i, hp) in self.hp_lost.iter().enumerate() {
+			line(v2, &format!("  unit {}: {:.2}",i + 1,hp));
+		}
+		let durability = self.durability_lost(team);
+		if !durability.is_empty() {
+			line(v2, "durability lost:");
+			for s in durability.iter() {
+				line(v2, &format!("  {}",s));
+			}
+		}
+		if !self.perks.is_empty() {
+			line(v2, "new perks:");
+			let mut p = vec2(x, y - PERK_SIZE + TEXT_SIZE.y);
+			for perk in self.perks.iter() {
+				perk.draw(v, v2, vp, p, Vec2::one(), m, d, false);
+				p.x += perk.size(Vec2::one(), false).x * 1.1;
+			}
+			y -= PERK_SIZE * 1.1;
+		}
+		if !self.equipment.is_empty() {
+			draw_string(v2, vec3(x, y, 10.0), TEXT_SIZE, "new equipment:", None);
+			y -= TEXT_SIZE.y * 1.3;
+			let mut p = vec2(x, y - EQUIP_SIZE + TEXT_SIZE.y);
+			for e in self.equipment.iter() {
+				e.draw(v, v2, vp, p, Vec2::one(), m, None, false);
+				p.x += e.size(Vec2::one(), false).x * 1.1;
+			}
+		}
+		self.continue_button().draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, d, false);
+	}
+}
@@ -1,11 +1,12 @@
 use crate::prelude::*;
 use crate::vertex::*;
+use crate::settings::{element_color,class_color};
 
 pub trait Thing {
 	type Args;
 	fn size(&self, size: Vec2<f32>, args: Self::Args) -> Vec2<f32>;
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, pos: Vec2<f32>, size: Vec2<f32>, mouse: Vec2<f32>, drag_from: Option<Vec2<f32>>, args: Self::Args);
-	fn collides(&self, m: Vec2<f32>, pos: Vec2<f32>, size: Vec2<f32>, args: Self::Args) -> Option<usize> {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec2<f32>, size: Vec2<f32>, mouse: Vec2<f32>, drag_from: Option<Vec2<f32>>, args: Self::Args);
+	fn collides(&self, _vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>, size: Vec2<f32>, args: Self::Args) -> Option<usize> {
 		if rect(m, pos, self.size(size, args)) { Some(0) } else { None }
 	}
 	fn select(&self, _args: Self::Args) -> Option<Box<dyn Thing<Args=Self::Args>>> {
@@ -13,6 +14,22 @@ pub trait Thing {
 	}
 }
 
+//lets code that only cares about the underlying stats treat both sides of a fight alike
+pub trait AsUnit {
+	fn unit(&self) -> &Unit;
+	fn unit_mut(&mut self) -> &mut Unit;
+}
+
+impl AsUnit for Unit {
+	fn unit(&self) -> &Unit { self }
+	fn unit_mut(&mut self) -> &mut Unit { self }
+}
+
+impl AsUnit for UnitView {
+	fn unit(&self) -> &Unit { &self.unit }
+	fn unit_mut(&mut self) -> &mut Unit { &mut self.unit }
+}
+
 //pub const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
 pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
 pub const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
@@ -43,9 +60,9 @@ impl Thing for Unit {
 		UNIT_SIZE * size
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, mut pos: Vec2<f32>, size: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, heal_button: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, mut pos: Vec2<f32>, size: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, heal_button: bool) {
 		let mut mouseover_shift_left = false;
-		drag_from.map(|d| if let Some(0) = self.collides(d, pos, size, false) {
+		drag_from.map(|d| if let Some(0) = self.collides(vp, d, pos, size, false) {
 			pos += m - d;
 			mouseover_shift_left = true;
 		});
@@ -54,37 +71,28 @@ impl Thing for Unit {
 			class_revealed: true,
 			element_revealed: true,
 			frac_hp_revealed: true,
-		}.draw(v, v2, pos, size, m, drag_from, mouseover_shift_left);
+		}.draw(v, v2, vp, pos, size, m, drag_from, mouseover_shift_left);
 		if let Some(perks) = self.perk_choice.as_ref() {
 			let mut p = pos + vec2(0.0, - perks[0].size(size, false).y * 1.1);
 			for perk in perks.iter() {
-				perk.draw(v, v2, p, size, m, drag_from, false);
+				perk.draw(v, v2, vp, p, size, m, drag_from, false);
 				p += vec2(perk.size(size, false).x * 1.1, 0.0);
 			}
 		}
-		if heal_button {
-			let cost = (1.0 - self.hp / self.max_hp) * HEAL_COST * (self.perks.len() + 5 + self.perk_choice.is_some() as usize) as f64 / 5.0;
-			if cost > 1e-8 {
-				Button {
-					name: format!("heal: {:.2}",cost),
-					pos: pos + vec2(0.0, self.size(size, heal_button).y + 0.1),
-					size: self.size(size, heal_button) / vec2(1.0, 6.0),
-					tex: Color(GREEN),
-					edge: None,
-				}.draw(v, v2, Vec2::zero(), Vec2::one(), m, drag_from, false);
-			}
+		if heal_button && crate::cost::heal(self) > 1e-8 {
+			unit_heal_button(self, pos, size).draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, drag_from, false);
 		}
 	}
 	
-	fn collides(&self, m: Vec2<f32>, pos: Vec2<f32>, size: Vec2<f32>, heal_button: bool) -> Option<usize> {
+	fn collides(&self, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>, size: Vec2<f32>, heal_button: bool) -> Option<usize> {
 		if rect(m, pos, self.size(size, false)) {
 			Some(0)
-		} else if rect(m, pos + vec2(0.0, self.size(size, heal_button).y + 0.1), self.size(size, heal_button) / vec2(1.0, 6.0)) && heal_button {
+		} else if heal_button && unit_heal_button(self, pos, size).collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
 			Some(1)
 		} else if let Some(perks) = self.perk_choice.as_ref() {
 			let mut p = pos + vec2(0.0, -perks[0].size(size, false).y * 1.1);
 			for i in 0..perks.len() {
-				if perks[i].collides(m, p, size, false).is_some() {
+				if perks[i].collides(vp, m, p, size, false).is_some() {
 					return Some(i + 2);
 				}
 				p += vec2(perks[i].size(size, false).x * 1.1, 0.0);
@@ -106,21 +114,12 @@ impl Thing for UnitView {
 		self.unit.size(size, false)
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, pos: Vec2<f32>, s: Vec2<f32>, mut m: Vec2<f32>, _drag_from: Option<Vec2<f32>>, mouseover_shift_left: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec2<f32>, s: Vec2<f32>, mut m: Vec2<f32>, _drag_from: Option<Vec2<f32>>, mouseover_shift_left: bool) {
 		let size = self.size(s, false) / vec2(1.0, 3.0);
 		let offset = vec2(0.0, size.y);
-		let c = match (self.class_revealed, self.class) {
-			(true, Melee) => DULL_RED,
-			(true, Ranged) => DARK_GREEN,
-			(false, _) => GREY,
-		};
+		let c = if self.class_revealed { class_color(vp, self.class) } else { GREY };
 		quad(v, pos.extend(1.0), size, Color(c));
-		let c = match (self.element_revealed, self.element) {
-			(true, Red) => RED,
-			(true, Green) => GREEN,
-			(true, Blue) => BLUE,
-			(false, _) => GREY,
-		};
+		let c = if self.element_revealed { element_color(vp, self.element) } else { GREY };
 		quad(v, (pos + offset).extend(1.0), size, Color(c));
 		quad(v, (pos + offset * 2.0).extend(1.0), size, Color(if self.frac_hp_revealed { DARK_GREY } else { GREY }));
 		if self.frac_hp_revealed {
@@ -130,21 +129,23 @@ impl Thing for UnitView {
 		let mut perks = String::new();
 		(0..self.perks.len()).map(|i| if i % 10 == 0 { perks.push('\n'); perks.push('*') } else { perks.push('*') }).last();
 		draw_string(v2, (pos + offset * 3.0).extend(2.0), vec2(0.03, 0.03) * s, &perks, None);
-		if let Some(0) = self.collides(m, pos, s, false) {
-			if (m + self.size(Vec2::one(), false) * vec2(4.0, 1.0)).y > top_edge() {
-				m.y -= (self.size(Vec2::one(), false) * vec2(4.0, 1.0)).y;
+		if let Some(0) = self.collides(vp, m, pos, s, false) {
+			let scale = vp.display.tooltip_scale;
+			let mouseover = self.size(Vec2::one(), false) * vec2(4.0, 1.0) * scale;
+			if (m + mouseover).y > vp.top_edge() {
+				m.y -= mouseover.y;
 			}
-			if (m + self.size(Vec2::one(), false) * vec2(4.0, 1.0)).x > right_edge() || mouseover_shift_left {
-				m.x -= (self.size(Vec2::one(), false) * vec2(4.0, 1.0)).x;
+			if (m + mouseover).x > vp.right_edge() || mouseover_shift_left {
+				m.x -= mouseover.x;
 			}
 			let c = [DARK_GREY[0], DARK_GREY[1], DARK_GREY[2], 0.75];
-			quad(v2, m.extend(10.0), self.size(Vec2::one(), false) * vec2(4.0, 1.0), Color(c));
+			quad(v2, m.extend(10.0), mouseover, Color(c));
 			let c = match (self.class_revealed, self.class) {
 				(true, Melee) => "melee",
 				(true, Ranged) => "ranged",
 				(false, _) => "??",
 			};
-			let size = size / s / 3.5;
+			let size = size / s / 3.5 * scale;
 			let offset = vec2(0.0,size.y);
 			draw_string(v2, m.extend(11.0), size, &format!("class: {}",c), None);
 			let c = match (self.element_revealed, self.element) {
@@ -163,8 +164,8 @@ impl Thing for UnitView {
 		}
 	}
 	
-	fn collides(&self, m: Vec2<f32>, pos: Vec2<f32>, size: Vec2<f32>, _: bool) -> Option<usize> {
-		self.unit.collides(m, pos, size, false)
+	fn collides(&self, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>, size: Vec2<f32>, _: bool) -> Option<usize> {
+		self.unit.collides(vp, m, pos, size, false)
 	}
 	
 	fn select(&self, _: bool) -> Option<Box<dyn Thing<Args=Self::Args>>> {
@@ -178,16 +179,16 @@ impl Thing for Perk {
 		vec2(PERK_SIZE, PERK_SIZE) * size
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, pos: Vec2<f32>, size: Vec2<f32>, m: Vec2<f32>, _drag_from: Option<Vec2<f32>>, _: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec2<f32>, size: Vec2<f32>, m: Vec2<f32>, _drag_from: Option<Vec2<f32>>, _: bool) {
 		quad(v, pos.extend(12.0), self.size(size, false), Color([self.color[0], self.color[1], self.color[2], 1.0]));
-		if self.collides(m, pos, size, false).is_some() {
-			draw_perk_mouseover(self, v2, m.extend(13.0));
+		if self.collides(vp, m, pos, size, false).is_some() {
+			draw_perk_mouseover(self, v2, vp, m.extend(13.0));
 		}
 	}
 }
 
-fn draw_perk_mouseover(p: &Perk, v2: &mut Vec<Vertex>, pos: Vec3<f32>) {
-	draw_string(v2, pos, vec2(p.size(Vec2::one(), false).x, p.size(Vec2::one(), false).x) * 0.5, &p.desc, Some((Color([p.color[0] * 0.8, p.color[1] * 0.8, p.color[2] * 0.8, 0.7]), DESC_WIDTH)));
+fn draw_perk_mouseover(p: &Perk, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec3<f32>) {
+	draw_string(v2, pos, vec2(p.size(Vec2::one(), false).x, p.size(Vec2::one(), false).x) * 0.5 * vp.display.tooltip_scale, &p.desc, Some((Color([p.color[0] * 0.8, p.color[1] * 0.8, p.color[2] * 0.8, 0.7]), DESC_WIDTH)));
 }
 
 impl Thing for Equipment {
@@ -197,16 +198,16 @@ impl Thing for Equipment {
 		x.size(size, drag) * 0.9
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, mut pos: Vec2<f32>, s: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, mut pos: Vec2<f32>, s: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
 		let mut mouseover_shift_left = false;
-		if drag { drag_from.map(|d| if let Some(0) = self.collides(d, pos, s, false) {
+		if drag { drag_from.map(|d| if let Some(0) = self.collides(vp, d, pos, s, false) {
 			pos += m - d;
 			mouseover_shift_left = true;
 		}); }
 		let size = self.size(s, drag);
 		quad(v, pos.extend(10.0), size, Color([self.color[0], self.color[1], self.color[2], 1.0]));
-		if self.collides(m, pos, s, false).is_some() {
-			draw_equip_mouseover(self, v2, m.extend(12.0), mouseover_shift_left);
+		if self.collides(vp, m, pos, s, false).is_some() {
+			draw_equip_mouseover(self, v2, vp, m.extend(12.0), mouseover_shift_left);
 		}
 	}
 }
@@ -217,22 +218,22 @@ impl Thing for Option<Equipment> {
 		vec2(EQUIP_SIZE, EQUIP_SIZE) * size
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, pos: Vec2<f32>, size: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec2<f32>, size: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
 		quad(v, pos.extend(1.0), self.size(size, drag), Color(DARK_GREY));
-		self.as_ref().map(|e| e.draw(v, v2, pos + self.size(size, drag) * 0.05, size, m, drag_from, drag));
+		self.as_ref().map(|e| e.draw(v, v2, vp, pos + self.size(size, drag) * 0.05, size, m, drag_from, drag));
 	}
 }
 
-fn draw_equip_mouseover(e: &Equipment, v2: &mut Vec<Vertex>, mut pos: Vec3<f32>, mouseover_shift_left: bool) {
-	let size = e.size(Vec2::one(), false) * 0.3;
+fn draw_equip_mouseover(e: &Equipment, v2: &mut Vec<Vertex>, vp: &Viewport, mut pos: Vec3<f32>, mouseover_shift_left: bool) {
+	let size = e.size(Vec2::one(), false) * 0.3 * vp.display.tooltip_scale;
 	let size = vec2(size.x, size.x);
-	if pos.x + size.x * DESC_WIDTH as f32 > right_edge() || mouseover_shift_left {
+	if pos.x + size.x * DESC_WIDTH as f32 > vp.right_edge() || mouseover_shift_left {
 		pos.x -= size.x * DESC_WIDTH as f32;
 	}
 	let mut n = if e.desc.is_empty() { 0.0 } else {
 		e.desc.chars().map(|c| if c == '\n' { 1.0 } else { 0.0 }).sum::<f32>() + 1.0
 	} + e.stat_name1_secondary().is_some() as u8 as f32 + e.stat_name2_secondary().is_some() as u8 as f32;
-	if pos.y + size.y * (n+3.0) > top_edge() {
+	if pos.y + size.y * (n+3.0) > vp.top_edge() {
 		pos.y -= size.y * (n+3.0);
 	}
 	let offset = vec3(0.0, size.y, 0.0);
@@ -254,36 +255,75 @@ fn draw_equip_mouseover(e: &Equipment, v2: &mut Vec<Vertex>, mut pos: Vec3<f32>,
 	draw_string(v2, pos + offset * (n+3.0), size, &format!("{}: {:.3}",e.stat_name1(),e.stat1.0), c);
 }
 
+//what's currently in the slot the hovered item would go in, with how much each stat would change by swapping
+pub fn draw_equip_comparison(e: &Equipment, current: Option<&Equipment>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+	let size = e.size(Vec2::one(), false) * 0.3 * vp.display.tooltip_scale;
+	let size = vec2(size.x, size.x);
+	let width = size.x * DESC_WIDTH as f32;
+	let mut pos = m.extend(12.0) - vec3(width, 0.0, 0.0);
+	if m.x + width > vp.right_edge() {
+		pos.x -= width;
+	}
+	let current = match current {
+		Some(c) => c,
+		None => {
+			draw_string(v2, pos, size, "nothing equipped there", Some((Color([GREY[0], GREY[1], GREY[2], 0.7]), DESC_WIDTH)));
+			return;
+		},
+	};
+	//name, current value, change from swapping and whether a drop is the improvement
+	let mut lines = vec!((current.stat_name1().to_string(), current.stat1.0, e.stat1.0 - current.stat1.0, false));
+	if let Some(s) = current.stat_name1_secondary() {
+		lines.push((s.to_string(), current.stat1.1, e.stat1.1 - current.stat1.1, false));
+	}
+	lines.push((current.stat_name2().to_string(), current.stat2.0, e.stat2.0 - current.stat2.0, false));
+	if let Some(s) = current.stat_name2_secondary() {
+		lines.push((s.to_string(), current.stat2.1, e.stat2.1 - current.stat2.1, false));
+	}
+	lines.push(("durability".to_string(), current.durability, e.durability - current.durability, false));
+	lines.push(("repair_cost".to_string(), current.repair_cost, e.repair_cost - current.repair_cost, true));
+	if pos.y + size.y * (lines.len() + 1) as f32 > vp.top_edge() {
+		pos.y -= size.y * (lines.len() + 1) as f32;
+	}
+	let offset = vec3(0.0, size.y, 0.0);
+	for (i, (name, value, delta, lower_is_better)) in lines.iter().enumerate() {
+		let gain = if *lower_is_better { -delta } else { *delta };
+		let c = if gain > 1e-8 { DARK_GREEN } else if gain < -1e-8 { DULL_RED } else { DARK_GREY };
+		draw_string(v2, pos + offset * i as f32, size, &format!("{}: {:.3} ({:+.3})",name,value,delta), Some((Color([c[0], c[1], c[2], 0.7]), DESC_WIDTH)));
+	}
+	draw_string(v2, pos + offset * lines.len() as f32, size, "equipped:", Some((Color([DARK_GREY[0], DARK_GREY[1], DARK_GREY[2], 0.7]), DESC_WIDTH)));
+}
+
 impl Thing for (Vec<Perk>, [Option<Equipment>; 4]) {
 	type Args = bool;
 	fn size(&self, size: Vec2<f32>, _: bool) -> Vec2<f32> {
 		size
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, pos: Vec2<f32>, s: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec2<f32>, s: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
 		let mut p = pos + 0.01;
 		for i in 0..self.0.len() {
-			self.0[i].draw(v, v2, p, s, m, drag_from, drag);
+			self.0[i].draw(v, v2, vp, p, s, m, drag_from, drag);
 			p.x += self.0[i].size(s, drag).x * 1.1;
 		}
 		p = pos + 0.01;
 		p.y += PERK_SIZE * 1.1;
 		for e in self.1.iter() {
-			e.draw(v, v2, p, s, m, drag_from, drag);
+			e.draw(v, v2, vp, p, s, m, drag_from, drag);
 			p.x += e.size(s, drag).x * 1.1;
 		}
 	}
 	
-	fn collides(&self, m: Vec2<f32>, pos: Vec2<f32>, s: Vec2<f32>, collide_slots: bool) -> Option<usize> {
+	fn collides(&self, vp: &Viewport, m: Vec2<f32>, pos: Vec2<f32>, s: Vec2<f32>, collide_slots: bool) -> Option<usize> {
 		let mut p = pos + 0.01;
 		p.y += PERK_SIZE * 1.1;
 		for i in 0..self.1.len() {
 			if collide_slots {
-				if self.1[i].collides(m, p, s, false).is_some() {
+				if self.1[i].collides(vp, m, p, s, false).is_some() {
 					return Some(i)
 				}
 			} else {
-				if self.1[i].as_ref().and_then(|e| e.collides(m, p, s, false)).is_some() {
+				if self.1[i].as_ref().and_then(|e| e.collides(vp, m, p, s, false)).is_some() {
 					return Some(i)
 				}
 			}
@@ -299,9 +339,9 @@ impl Thing for MoveOption {
 		BUTTON_SIZE * size
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, pos: Vec2<f32>, s: Vec2<f32>, m: Vec2<f32>, _drag_from: Option<Vec2<f32>>, _: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, pos: Vec2<f32>, s: Vec2<f32>, m: Vec2<f32>, _drag_from: Option<Vec2<f32>>, _: bool) {
 		let size = self.size(s, false);
-		let c = [GREY[0], GREY[1], GREY[2], GREY[3] * if self.collides(m, pos, s, false).is_some() { 0.6 } else { 1.0 }];
+		let c = [GREY[0], GREY[1], GREY[2], GREY[3] * if self.collides(vp, m, pos, s, false).is_some() { 0.6 } else { 1.0 }];
 		quad(v, pos.extend(1.0), size, Color(c));
 		let offset = vec2(0.0, size.y * 0.5);
 		let pos = pos + vec2(0.0, size.y);
@@ -311,6 +351,16 @@ impl Thing for MoveOption {
 	}
 }
 
+pub fn unit_heal_button(u: &Unit, pos: Vec2<f32>, size: Vec2<f32>) -> Button {
+	Button {
+		name: format!("heal: {:.2}",crate::cost::heal(u)),
+		pos: pos + vec2(0.0, u.size(size, true).y + 0.1),
+		size: u.size(size, true) / vec2(1.0, 6.0),
+		tex: Color(GREEN),
+		edge: None,
+	}
+}
+
 pub struct Button {
 	pub name: String,
 	pub pos: Vec2<f32>,
@@ -325,33 +375,43 @@ impl Thing for Button {
 		self.size
 	}
 	
-	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, _: Vec2<f32>, _: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
+	fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, _: Vec2<f32>, _: Vec2<f32>, m: Vec2<f32>, drag_from: Option<Vec2<f32>>, drag: bool) {
 		let size = self.size(Vec2::one(), false);
 		let t = match self.tex {
-			Color(c) => Color([c[0], c[1], c[2], c[3] * if self.collides(m, Vec2::zero(), Vec2::one(), false).is_some() { 0.6 } else { 1.0 }]),
+			Color(c) => Color([c[0], c[1], c[2], c[3] * if self.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() { 0.6 } else { 1.0 }]),
 			Texture(n) => Texture(n),
-			Blend(c, n, blend) => Blend([c[0], c[1], c[2], c[3] * if self.collides(m, Vec2::zero(), Vec2::one(), false).is_some() { 0.6 } else { 1.0 }], n, blend),
+			Blend(c, n, blend) => Blend([c[0], c[1], c[2], c[3] * if self.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() { 0.6 } else { 1.0 }], n, blend),
 		};
-		let mut pos = self.pos();
-		drag_from.map(|d| if drag && self.collides(d, Vec2::zero(), Vec2::one(), false).is_some() { pos += m - d });
+		let mut pos = self.pos(vp);
+		drag_from.map(|d| if drag && self.collides(vp, d, Vec2::zero(), Vec2::one(), false).is_some() { pos += m - d });
 		quad(v, pos.extend(1.0), size, t);
 		let char_size = ((size.x * 0.9) / self.name.len() as f32).min(size.y * 0.9);
 		draw_string(v2, (pos + vec2((size.x - self.name.len() as f32 * char_size) * 0.5, (size.y - char_size) / 2.0)).extend(10.0), vec2(char_size, char_size), &self.name, None);
 	}
 	
-	fn collides(&self, m: Vec2<f32>, _: Vec2<f32>, _: Vec2<f32>, _: bool) -> Option<usize> {
-		if rect(m, self.pos(), self.size(Vec2::one(), false)) { Some(0) } else { None }
+	fn collides(&self, vp: &Viewport, m: Vec2<f32>, _: Vec2<f32>, _: Vec2<f32>, _: bool) -> Option<usize> {
+		if rect(m, self.pos(vp), self.size(Vec2::one(), false)) { Some(0) } else { None }
 	}
 }
 
 impl Button {
-	pub fn edgeified(mut self, e: bool) -> Self {
-		self.pos.x -= if e { left_edge() } else { right_edge() };
+	pub fn edgeified(mut self, vp: &Viewport, e: bool) -> Self {
+		self.pos.x -= if e { vp.left_edge() } else { vp.right_edge() };
 		self.edge = Some(e);
 		self
 	}
 	
-	pub fn pos(&self) -> Vec2<f32> {
-		self.pos + self.edge.map(|e| if e { vec2(left_edge(), 0.0) } else { vec2(right_edge(), 0.0) }).unwrap_or(Vec2::zero())
+	pub fn pos(&self, vp: &Viewport) -> Vec2<f32> {
+		self.pos + self.edge.map(|e| if e { vec2(vp.left_edge(), 0.0) } else { vec2(vp.right_edge(), 0.0) }).unwrap_or(Vec2::zero())
+	}
+	
+	//for buttons on panels that cover everything else, the normal draw puts the button itself underneath them
+	pub fn draw_over(&self, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>, z: f32) {
+		let c = match self.tex { Color(c) | Blend(c, _, _) => c, Texture(_) => GREY };
+		let c = [c[0], c[1], c[2], c[3] * if self.collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() { 0.6 } else { 1.0 }];
+		let pos = self.pos(vp);
+		quad(v2, pos.extend(z), self.size, Color(c));
+		let char_size = ((self.size.x * 0.9) / self.name.len() as f32).min(self.size.y * 0.9);
+		draw_string(v2, (pos + vec2((self.size.x - self.name.len() as f32 * char_size) * 0.5, (self.size.y - char_size) / 2.0)).extend(z + 1.0), vec2(char_size, char_size), &self.name, None);
 	}
 }
@@ -0,0 +1,159 @@
+use crate::prelude::*;
+use crate::vertex::*;
+use crate::thing::*;
+
+pub const DEFAULT_DURATION: f64 = 30.0; //seconds
+const FADE: f64 = 2.0; //seconds at the end of a toast's life spent fading out
+const MAX_VISIBLE: usize = 5;
+const HISTORY_LINES: usize = 20;
+const MAX_HISTORY: usize = 500;
+
+const TEXT_SIZE: Vec2<f32> = Vec2{ x: 0.04, y: 0.04 };
+const HISTORY_TEXT: Vec2<f32> = Vec2{ x: 0.03, y: 0.03 };
+const HISTORY_SIZE: Vec2<f32> = Vec2{ x: 1.5, y: HISTORY_TEXT.y * 1.2 * (HISTORY_LINES + 2) as f32 };
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Severity {
+	Info,
+	Success,
+	Warning,
+	Error,
+}
+
+impl Severity {
+	fn color(self) -> [f32; 4] {
+		match self {
+			Severity::Info => [0.15, 0.15, 0.15, 1.0],
+			Severity::Success => [0.05, 0.35, 0.1, 1.0],
+			Severity::Warning => [0.5, 0.4, 0.05, 1.0],
+			Severity::Error => [0.5, 0.1, 0.1, 1.0],
+		}
+	}
+}
+
+struct Toast {
+	text: String,
+	severity: Severity,
+	left: f64,
+}
+
+//messages stack up from the bottom right and fade out on their own, older ones are pushed out past the cap
+pub struct Toasts {
+	toasts: Vec<Toast>,
+	history: Vec<String>, //everything the server said this session
+	scroll: usize,
+	pub duration: f64,
+	pub open: bool,
+}
+
+impl Toasts {
+	pub fn new() -> Self {
+		Self { toasts: Vec::new(), history: Vec::new(), scroll: 0, duration: DEFAULT_DURATION, open: false }
+	}
+
+	pub fn push(&mut self, text: String, severity: Severity) {
+		self.toasts.push(Toast { text, severity, left: self.duration });
+		if self.toasts.len() > MAX_VISIBLE {
+			self.toasts.remove(0);
+		}
+	}
+
+	pub fn info(&mut self, text: String) { self.push(text, Severity::Info); }
+	pub fn success(&mut self, text: String) { self.push(text, Severity::Success); }
+	pub fn warning(&mut self, text: String) { self.push(text, Severity::Warning); }
+	pub fn error(&mut self, text: String) { self.push(text, Severity::Error); }
+
+	//server messages are the only ones worth scrolling back through later
+	pub fn server(&mut self, text: String) {
+		self.history.push(text.clone());
+		if self.history.len() > MAX_HISTORY {
+			self.history.remove(0);
+		}
+		self.info(text);
+	}
+
+	pub fn update(&mut self, dt: f64) {
+		for t in self.toasts.iter_mut() {
+			t.left -= dt;
+		}
+		self.toasts.retain(|t| t.left > 0.0);
+	}
+
+	//newest at the bottom
+	fn toast_rects(&self, vp: &Viewport) -> Vec<(usize, Vec2<f32>, Vec2<f32>)> {
+		let mut p = vec2(vp.right_edge(), vp.bottom_edge());
+		let mut rects = Vec::new();
+		for (i, t) in self.toasts.iter().enumerate().rev() {
+			let size = vec2(TEXT_SIZE.x * t.text.len() as f32, TEXT_SIZE.y);
+			rects.push((i, p - vec2(size.x, 0.0), size));
+			p.y += TEXT_SIZE.y * 1.2;
+		}
+		rects
+	}
+
+	pub fn history_button(&self, vp: &Viewport) -> Button {
+		Button {
+			name: "log".to_string(),
+			pos: vec2(vp.right_edge() - 0.16, vp.top_edge() - 0.058),
+			size: vec2(0.14, 0.05),
+			tex: Color(if self.open { CYAN } else { DARK_GREY }),
+			edge: None,
+		}
+	}
+
+	fn history_pos(&self, vp: &Viewport) -> Vec2<f32> {
+		vec2(vp.right_edge() - HISTORY_SIZE.x - 0.02, vp.top_edge() - 0.1 - HISTORY_SIZE.y)
+	}
+
+	pub fn history_collides(&self, vp: &Viewport, m: Vec2<f32>) -> bool {
+		self.open && rect(m, self.history_pos(vp), HISTORY_SIZE)
+	}
+
+	pub fn scroll_by(&mut self, lines: isize) {
+		let max = self.history.len().saturating_sub(HISTORY_LINES) as isize;
+		self.scroll = (self.scroll as isize + lines).max(0).min(max) as usize;
+	}
+
+	//returns whether the click landed on something here, clicking a toast dismisses it
+	pub fn click(&mut self, vp: &Viewport, m: Vec2<f32>) -> bool {
+		if self.history_button(vp).collides(vp, m, Vec2::zero(), Vec2::one(), false).is_some() {
+			self.open = !self.open;
+			self.scroll = 0;
+			return true;
+		}
+		if self.history_collides(vp, m) {
+			return true;
+		}
+		match self.toast_rects(vp).into_iter().find(|&(_, p, s)| rect(m, p, s)) {
+			Some((i, _, _)) => {
+				self.toasts.remove(i);
+				true
+			},
+			None => false,
+		}
+	}
+
+	pub fn draw(&self, v: &mut Vec<Vertex>, v2: &mut Vec<Vertex>, vp: &Viewport, m: Vec2<f32>) {
+		for (i, p, size) in self.toast_rects(vp) {
+			let t = &self.toasts[i];
+			let fade = (t.left / FADE).min(1.0) as f32;
+			let c = t.severity.color();
+			quad(v2, p.extend(29.0), size, Color([c[0], c[1], c[2], c[3] * fade * 0.8]));
+			draw_string_blended(v2, p.extend(30.0), TEXT_SIZE, t.text.clone(), fade, [0.0; 4]);
+		}
+		self.history_button(vp).draw(v, v2, vp, Vec2::zero(), Vec2::one(), m, None, false);
+		if self.open {
+			let pos = self.history_pos(vp);
+			quad(v2, pos.extend(27.0), HISTORY_SIZE, Color([0.1, 0.1, 0.1, 0.95]));
+			if self.history.is_empty() {
+				draw_string(v2, (pos + vec2(0.02, HISTORY_SIZE.y - HISTORY_TEXT.y * 1.5)).extend(28.0), HISTORY_TEXT, "no messages yet", None);
+			}
+			//newest first, scrolling goes back in time
+			let max_chars = ((HISTORY_SIZE.x - 0.04) / HISTORY_TEXT.x) as usize;
+			for (j, s) in self.history.iter().rev().skip(self.scroll).take(HISTORY_LINES).enumerate() {
+				let s: String = s.chars().take(max_chars).collect();
+				draw_string(v2, (pos + vec2(0.02, HISTORY_SIZE.y - HISTORY_TEXT.y * 1.2 * (j + 1) as f32 - 0.02)).extend(28.0), HISTORY_TEXT, &s, None);
+			}
+		}
+	}
+}
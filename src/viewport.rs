@@ -0,0 +1,147 @@
+use crate::prelude::*;
+
+pub const MIN_ASPECT_RATIO: f32 = 1.5; //narrower screens get bars above and below rather than overlapping ui
+//everything is laid out to fill -1 to 1 vertically and the scale zooms around the centre, so anything over 1 pushes panel buttons off screen
+pub const MAX_UI_SCALE: f32 = 1.0;
+
+//the settings that change how things are drawn, carried along with the viewport since that already reaches all the drawing code
+#[derive(Debug,Copy,Clone)]
+pub struct DisplayPrefs {
+	pub colorblind: bool,
+	pub tooltip_scale: f32,
+}
+
+impl Default for DisplayPrefs {
+	fn default() -> Self {
+		Self { colorblind: false, tooltip_scale: 1.0 }
+	}
+}
+
+#[derive(Debug,Copy,Clone)]
+pub struct Viewport {
+	pub size: Vec2<f32>, //in css pixels
+	pub dpr: f32,
+	pub scale: f32,
+	pub display: DisplayPrefs,
+}
+
+impl Viewport {
+	pub fn new(size: Vec2<f32>, dpr: f32, scale: f32) -> Self {
+		Self {
+			size: vec2(size.x.max(1.0), size.y.max(1.0)),
+			dpr,
+			scale: scale.max(0.5).min(MAX_UI_SCALE),
+			display: DisplayPrefs::default(),
+		}
+	}
+
+	//the canvas backing store is sized in device pixels so text doesn't get upscaled on high dpi screens
+	pub fn backing_size(&self) -> Vec2<f32> {
+		vec2((self.size.x * self.dpr).round(), (self.size.y * self.dpr).round())
+	}
+
+	//returns the height of the area actually drawn to and the size of the bars above and below it, in css pixels
+	pub fn letterbox(&self) -> (f32, f32) {
+		let h = self.size.y.min(self.size.x / MIN_ASPECT_RATIO);
+		(h, (self.size.y - h) / 2.0)
+	}
+
+	pub fn aspect_ratio(&self) -> f32 {
+		self.size.x / self.letterbox().0
+	}
+
+	pub fn top_edge(&self) -> f32 {
+		self.scale.recip()
+	}
+
+	pub fn bottom_edge(&self) -> f32 {
+		- self.scale.recip()
+	}
+
+	pub fn left_edge(&self) -> f32 {
+		- self.aspect_ratio() / self.scale
+	}
+
+	pub fn right_edge(&self) -> f32 {
+		self.aspect_ratio() / self.scale
+	}
+
+	pub fn screen_coords(&self, x: i32, y: i32) -> Vec2<f32> {
+		let (h, offset) = self.letterbox();
+		let (x, y) = (x as f32 / self.size.x * 2.0 - 1.0, 1.0 - (y as f32 - offset) / h * 2.0);
+		let x = x * self.aspect_ratio(); //multiply by aspect ratio so it will line up with aspect ratio rendered
+		vec2(x,y) / self.scale
+	}
+}
+
+impl Default for Viewport {
+	fn default() -> Self {
+		Self::new(vec2(MIN_ASPECT_RATIO, 1.0), 1.0, 1.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: Vec2<f32>, b: Vec2<f32>) -> bool {
+		(a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5
+	}
+
+	#[test]
+	fn edges_at_the_minimum_aspect_ratio() {
+		let vp = Viewport::new(vec2(1500.0, 1000.0), 1.0, 1.0);
+		assert_eq!(vp.letterbox(), (1000.0, 0.0));
+		assert_eq!((vp.left_edge(), vp.right_edge(), vp.bottom_edge(), vp.top_edge()), (-1.5, 1.5, -1.0, 1.0));
+	}
+
+	#[test]
+	fn wide_screens_get_more_room_at_the_sides() {
+		let vp = Viewport::new(vec2(2000.0, 1000.0), 1.5, 1.0);
+		assert_eq!(vp.letterbox(), (1000.0, 0.0));
+		assert_eq!(vp.backing_size(), vec2(3000.0, 1500.0));
+		assert_eq!((vp.left_edge(), vp.right_edge()), (-2.0, 2.0));
+	}
+
+	#[test]
+	fn narrow_screens_are_letterboxed() {
+		let vp = Viewport::new(vec2(600.0, 1000.0), 2.0, 1.0);
+		assert_eq!(vp.letterbox(), (400.0, 300.0));
+		assert_eq!(vp.backing_size(), vec2(1200.0, 2000.0));
+		assert_eq!(vp.aspect_ratio(), MIN_ASPECT_RATIO);
+	}
+
+	#[test]
+	fn backing_size_rounds_to_whole_device_pixels() {
+		let vp = Viewport::new(vec2(1001.0, 667.0), 1.25, 1.0);
+		assert_eq!(vp.backing_size(), vec2(1251.0, 834.0));
+	}
+
+	#[test]
+	fn screen_corners_map_to_the_edges() {
+		for &(size, dpr) in [(vec2(1500.0, 1000.0), 1.0), (vec2(2000.0, 1000.0), 1.5), (vec2(600.0, 1000.0), 2.0)].iter() {
+			let vp = Viewport::new(size, dpr, 1.0);
+			let (h, bar) = vp.letterbox();
+			let top = bar.round() as i32;
+			let bottom = (bar + h).round() as i32;
+			assert!(close(vp.screen_coords(0, top), vec2(vp.left_edge(), vp.top_edge())));
+			assert!(close(vp.screen_coords(size.x as i32, bottom), vec2(vp.right_edge(), vp.bottom_edge())));
+			assert!(close(vp.screen_coords(size.x as i32 / 2, (top + bottom) / 2), Vec2::zero()));
+		}
+	}
+
+	#[test]
+	fn clicks_in_the_bars_land_outside_the_drawn_area() {
+		let vp = Viewport::new(vec2(600.0, 1000.0), 2.0, 1.0);
+		assert!(vp.screen_coords(300, 100).y > vp.top_edge());
+		assert!(vp.screen_coords(300, 900).y < vp.bottom_edge());
+	}
+
+	#[test]
+	fn ui_scale_zooms_out_around_the_centre() {
+		let vp = Viewport::new(vec2(2000.0, 1000.0), 1.0, 0.5);
+		assert_eq!((vp.left_edge(), vp.right_edge(), vp.bottom_edge(), vp.top_edge()), (-4.0, 4.0, -2.0, 2.0));
+		assert!(close(vp.screen_coords(0, 0), vec2(-4.0, 2.0)));
+		assert_eq!(Viewport::new(vec2(2000.0, 1000.0), 1.0, 10.0).scale, MAX_UI_SCALE);
+	}
+}
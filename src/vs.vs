@@ -8,10 +8,11 @@ varying vec2 uv;
 varying float blend;
 
 uniform float aspect_ratio;
+uniform float scale;
 
 void main() {
 	col = vcol;
 	uv = vuv;
 	blend = vblend;
-	gl_Position = vec4((pos.xy)*vec2(aspect_ratio,1.0),pos.z*0.0001,1.0);
+	gl_Position = vec4((pos.xy)*vec2(aspect_ratio,1.0)*scale,pos.z*0.0001,1.0);
 }